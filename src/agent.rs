@@ -1,124 +1,291 @@
 use crate::{
     astar::astar,
-    grid::Grid,
-    location::{closest, Located, Location},
-    State, COLS,
+    components::Glyph,
+    ecs::Entity,
+    location::{best, closest, Direction, Location},
+    message::{Content, Outgoing},
+    metrics::AgentMetrics,
+    world::World,
 };
-use bracket_lib::prelude::*;
+use bracket_color::prelude::*;
 use log::{debug, warn};
-use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, str::FromStr, time::Duration};
+// std's panics in the browser
+use web_time::Instant;
 
+/// Agent ids, there can be hundreds of agents.
+pub(crate) type AgentId = u16;
+
+/// The first agents keep the colors they always had, the others get generated ones.
+const COLORS: [(u8, u8, u8); 6] = [RED, GREEN, BLUE, CYAN, MAGENTA, PLUM];
+/// Every next group of agents gets another glyph, so agents with similar colors can be told apart.
+const GLYPHS: [u16; 6] = [254, 4, 5, 6, 3, 15];
+
+/// What makes an entity act on its own, where it is and what it carries are other components.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Agent {
-    id: u8,
-    pub(crate) location: Location,
+    pub(crate) id: AgentId,
     pub(crate) state: AgentState,
-    score: u32,
-    tile_score: Option<u8>,
+    policy: Policy,
+    pub(crate) score: u32,
+    pub(crate) metrics: AgentMetrics,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub(crate) enum AgentState {
     MoveToTile,
     MoveToHole,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Policy {
+    /// go for the nearest tile or hole
+    #[default]
+    Closest,
+    /// go for the tile or hole worth the most per step once we get there
+    BestValue,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Action {
+    #[default]
+    Wait,
+    /// step into the next cell, picking up a tile or filling a hole that's there
+    Move(Direction),
+}
+
+/// What an agent decided to do this tick and how it got there.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Plan {
+    pub action: Action,
+    /// where the agent is heading, if it found anything to go for
+    pub target: Option<Location>,
+    /// the rest of the way to `target` after this move
+    pub path: Vec<Direction>,
+    pub expanded: Vec<Location>,
+    pub planning_time: Duration,
+    /// what the agent tells others this tick
+    pub messages: Vec<Outgoing>,
+}
+
+impl Plan {
+    /// There was something to go for, but no way to get there.
+    pub(crate) fn failed(&self) -> bool {
+        self.target.is_some() && self.action == Action::Wait
+    }
+}
+
+impl FromStr for Policy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "closest" => Ok(Policy::Closest),
+            "value" => Ok(Policy::BestValue),
+            _ => Err(format!("unknown policy: {}", s)),
+        }
+    }
+}
+
 impl Agent {
-    pub(crate) fn new(id: u8, location: Location) -> Self {
+    pub(crate) fn new(id: AgentId, policy: Policy) -> Self {
         Self {
             id,
-            location,
             state: AgentState::MoveToTile,
+            policy,
             score: 0,
-            tile_score: None,
+            metrics: AgentMetrics::default(),
+        }
+    }
+
+    pub(crate) fn color(&self) -> (u8, u8, u8) {
+        if let Some(&color) = COLORS.get(self.id as usize) {
+            return color;
         }
+        // stepping around the hue circle by the golden ratio keeps nearby ids apart
+        let hue = (self.id as f32 * 0.618_034).fract();
+        let value = if (self.id / 5).is_multiple_of(2) {
+            0.85
+        } else {
+            0.6
+        };
+        let rgb = HSV::from_f32(hue, 0.9, value).to_rgb();
+        let byte = |c: f32| (c * 255.0).round() as u8;
+        (byte(rgb.r), byte(rgb.g), byte(rgb.b))
     }
 
-    pub(crate) fn update(&mut self, state: &State) {
+    pub(crate) fn glyph(&self) -> Glyph {
+        let group = self.id as usize / COLORS.len();
+        Glyph::new(GLYPHS[group % GLYPHS.len()], self.color())
+    }
+
+    /// Works out the next move for `me` from the current state of the world, without changing anything.
+    pub(crate) fn decide(&self, me: Entity, world: &World) -> Plan {
+        let entities = &world.entities;
+        let Some(&location) = entities.positions.get(me) else {
+            return Plan::default();
+        };
         debug!(
             "Agent {}: Location: {:?} state: {:?}",
-            self.id, self.location, self.state
+            self.id, location, self.state
         );
-        let mut grid = state.grid.borrow_mut();
-        match self.state {
-            AgentState::MoveToTile => {
-                if let Some(closest) = closest(self.location, &state.tiles) {
-                    debug!("Agent {}: Closest tile: {:?}", self.id, closest.borrow());
-                    let arrived = self.move_to(&mut grid, closest.borrow().location());
-                    if arrived {
-                        self.tile_score = Some(closest.borrow().score);
-                        grid.remove(self.location);
-                        let new_tile = grid.random_location();
-                        closest.borrow_mut().location = new_tile;
-                        closest.borrow_mut().score = rand::thread_rng().gen_range(1..5);
-                        grid.set(new_tile);
-                        self.state = AgentState::MoveToHole;
-                    }
-                } else {
-                    warn!("Agent {}: No tile found", self.id);
+        let started = Instant::now();
+        let carrying = entities.carriers.get(me).and_then(|c| c.carrying);
+        let teams = world.config.teams;
+        let teammates = || {
+            entities
+                .located(&entities.agents)
+                .filter(move |&(e, _)| world.teammates(me, e))
+        };
+        let mut claimed = HashSet::new();
+        if teams.share_claims {
+            claimed.extend(teammates().filter_map(|(e, _)| world.plans.get(&e)?.target));
+        }
+        // what others told us, an agent keeps going for what it heard about
+        let previous = world.plans.get(&me).and_then(|p| p.target);
+        let mut heard = HashSet::new();
+        if world.config.comms.enabled {
+            heard.extend(previous);
+        }
+        // with teams only teammates are listened to
+        let trusted = |from| teams.count == 0 || teams.team(from) == teams.team(self.id);
+        for message in world
+            .channel
+            .inbox(self.id)
+            .iter()
+            .filter(|m| trusted(m.from))
+        {
+            match message.content {
+                // of two agents claiming the same target the lower id keeps it
+                Content::Claim(l) if Some(l) == previous && message.from > self.id => {}
+                Content::Claim(l) => {
+                    claimed.insert(l);
                 }
-            }
-            AgentState::MoveToHole => {
-                if let Some(closest) = closest(self.location, &state.holes) {
-                    debug!("Agent {}: Closest hole: {:?}", self.id, closest.borrow());
-                    let arrived = self.move_to(&mut grid, closest.borrow().location());
-                    if arrived {
-                        self.score += self.tile_score.unwrap() as u32;
-                        self.tile_score = None;
-                        let new_hole = grid.random_location();
-                        closest.borrow_mut().location = new_hole;
-                        grid.set(new_hole);
-                        self.state = AgentState::MoveToTile;
-                        debug!("Agent {}: Score: {}", self.id, self.score);
-                    }
-                } else {
-                    warn!("Agent {}: No hole found", self.id);
+                Content::Tile(l) | Content::Hole(l) => {
+                    heard.insert(l);
                 }
             }
         }
-        if log::log_enabled!(log::Level::Debug) {
-            grid.print_grid();
+        let open = |&(_, l): &(Entity, Location)| {
+            (world.sees(me, l) || heard.contains(&l)) && !claimed.contains(&l)
+        };
+        // agents of other teams are worth what they carry to a thief
+        let robbable = entities.located(&entities.agents).filter(|&(e, _)| {
+            teams.steal
+                && e != me
+                && !world.teammates(me, e)
+                && entities
+                    .carriers
+                    .get(e)
+                    .is_some_and(|c| c.carrying.is_some() && c.stolen_from != Some(self.id))
+        });
+        let tiles = entities
+            .located(&entities.pickups)
+            .chain(robbable)
+            .filter(&open);
+        let holes = entities.located(&entities.receptacles).filter(&open);
+        let value = |e: Entity, distance: u16| {
+            let tick = world.tick + distance as u64;
+            let carried = || entities.carriers.get(e)?.carrying.as_ref();
+            entities
+                .values
+                .get(e)
+                .or_else(carried)
+                .map_or(0, |v| v.at(tick))
+        };
+        let target = match (carrying, self.policy) {
+            (None, Policy::Closest) => closest(location, tiles),
+            (None, Policy::BestValue) => best(location, tiles, value),
+            (Some(_), Policy::Closest) => closest(location, holes),
+            (Some(carried), Policy::BestValue) => best(location, holes, |e, distance| {
+                let tick = world.tick + distance as u64;
+                let hole = if world.config.decay.scores_holes() {
+                    value(e, distance)
+                } else {
+                    0
+                };
+                carried.at(tick) + hole
+            }),
+        };
+        let Some((target, to)) = target else {
+            warn!("Agent {}: No {:?} target found", self.id, self.state);
+            return Plan {
+                messages: self.messages(location, None, world),
+                ..Default::default()
+            };
+        };
+        debug!("Agent {}: Target: {} at {:?}", self.id, target, to);
+        let mut through = HashSet::new();
+        if teams.make_way {
+            through.extend(teammates().map(|(_, l)| l));
+        }
+        let search = astar(&world.grid, location, to, &through);
+        let mut plan = Plan {
+            target: Some(to),
+            expanded: search.expanded,
+            messages: self.messages(location, Some(to), world),
+            ..Default::default()
+        };
+        match search.path {
+            Some(mut path) if !path.is_empty() => {
+                debug!("Agent {}: Path: {:?}", self.id, path);
+                plan.action = Action::Move(path.remove(0));
+                plan.path = path;
+            }
+            Some(_) => warn!("Agent {}: empty path", self.id),
+            None => warn!("Agent {}: No path found", self.id),
         }
+        plan.planning_time = started.elapsed();
+        plan
     }
 
-    fn move_to(&mut self, grid: &mut Grid, to: Location) -> bool {
-        if let Some(mut path) = astar(grid, self.location, to) {
-            if path.len() == 0 {
-                warn!("Agent {}: empty path", self.id);
-                return false;
-            }
-            debug!("Agent {}: Path: {:?}", self.id, path);
-            let direction = path.remove(0); // guaranteed to have at least one element
-            let next = self.location.next_location(direction);
-            grid.remove(self.location);
-            self.location = next;
-            grid.set(self.location);
-            if next == to {
-                return true;
-            }
-        } else {
-            warn!("Agent {}: No path found", self.id);
+    /// What `me` tells its team, or everyone without teams: the target it goes for and, when sight is limited,
+    /// the tiles and holes it sees, nearest first.
+    fn messages(
+        &self,
+        location: Location,
+        target: Option<Location>,
+        world: &World,
+    ) -> Vec<Outgoing> {
+        if !world.config.comms.enabled {
+            return Vec::new();
+        }
+        let entities = &world.entities;
+        let mut contents: Vec<Content> = target.map(Content::Claim).into_iter().collect();
+        if let Some(sight) = world.config.teams.sight {
+            let tiles = entities.located(&entities.pickups);
+            let holes = entities.located(&entities.receptacles);
+            let mut seen: Vec<Content> = tiles
+                .map(|(_, l)| Content::Tile(l))
+                .chain(holes.map(|(_, l)| Content::Hole(l)))
+                .filter(|c| location.distance(c.location()) <= sight)
+                .collect();
+            seen.sort_by_key(|c| location.distance(c.location()));
+            contents.extend(seen);
         }
-        false
+        contents
+            .into_iter()
+            .map(|content| Outgoing { to: None, content })
+            .collect()
     }
+}
 
-    pub(crate) fn render(&self, ctx: &mut BTerm) {
-        let color = match self.id {
-            0 => RED,
-            1 => GREEN,
-            2 => BLUE,
-            3 => CYAN,
-            4 => MAGENTA,
-            5 => PLUM,
-            _ => BLACK,
-        };
-        let c: u16 = if self.tile_score.is_some() { 219 } else { 254 };
-        ctx.set(self.location.col, self.location.row, color, WHITE, c);
-        ctx.print_color(
-            COLS + 3,
-            self.id as u16 + 3,
-            color,
-            WHITE,
-            &format!("Agent {}: {}", self.id, self.score),
-        );
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visuals() {
+        let agents: Vec<Agent> = (0..300).map(|id| Agent::new(id, Policy::Closest)).collect();
+        assert_eq!(RED, agents[0].color());
+        assert_eq!(PLUM, agents[5].color());
+        for (i, a) in agents.iter().enumerate() {
+            assert_ne!(BLACK, a.color());
+            assert_ne!(WHITE, a.color());
+            for b in agents[..i].iter() {
+                assert_ne!(a.glyph(), b.glyph(), "agents {} and {}", a.id, b.id);
+            }
+        }
     }
 }
@@ -0,0 +1,114 @@
+use bracket_lib::prelude::*;
+use log::info;
+use std::{fs::File, io::BufWriter, time::Duration};
+
+use crate::{
+    config::{Config, Frontend},
+    dashboard,
+    events::EventLog,
+    experiment,
+    player::Player,
+    raster::Recorder,
+    replay::Replay,
+    server::Server,
+    snapshot::Snapshot,
+    state::State,
+    text, tui,
+    world::World,
+};
+
+/// Runs whatever the command line asks for, see the README for the options.
+pub fn run() -> BError {
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().is_some_and(|a| a == "experiment") {
+        return experiment::run(args.skip(1));
+    }
+    let config = Config::from_args(args)?;
+    if let Some(path) = &config.replay {
+        let replay = Replay::load(path)?;
+        let (cols, rows) = replay.dimensions();
+        return main_loop(window(cols, rows)?, replay);
+    }
+    let events = match &config.events {
+        Some(path) => EventLog::to_writer(BufWriter::new(File::create(path)?)),
+        None => EventLog::default(),
+    };
+    let world = match &config.load {
+        Some(path) => World::restore(Snapshot::load(path)?, config, events),
+        None => World::new(config, events),
+    };
+    let server = match &world.config.listen {
+        Some(addr) => {
+            let mut server = Server::bind(addr, Duration::from_millis(world.config.timeout))?;
+            if world.config.player {
+                server.reserve(0);
+            }
+            server.wait_for(&world, world.config.controllers);
+            Some(server)
+        }
+        None => None,
+    };
+    if world.config.headless {
+        return run_headless(world, server);
+    }
+    let (cols, rows) = (world.config.cols, world.config.rows);
+    let player = world.config.player.then(|| Player::new(0));
+    let state = State::new(world, server, player);
+    match state.world.config.frontend {
+        Frontend::Window => main_loop(window(cols, rows)?, state),
+        Frontend::Tui => Ok(tui::run(state)?),
+    }
+}
+
+fn window(cols: u16, rows: u16) -> BResult<BTerm> {
+    BTermBuilder::simple(cols + dashboard::SIDEBAR, rows + 1)?
+        .with_title("Tileworld")
+        .with_fps_cap(30.0)
+        .build()
+}
+
+fn run_headless(mut world: World, mut server: Option<Server>) -> BError {
+    let (frames, gif) = (world.config.frames.as_deref(), world.config.gif.as_deref());
+    let mut recorder = (frames.is_some() || gif.is_some())
+        .then(|| Recorder::new(frames, gif))
+        .transpose()?;
+    loop {
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.capture(&world)?;
+        }
+        if let Some(every) = world.config.ascii {
+            if world.tick.is_multiple_of(every) {
+                println!("{}\n", text::ascii(&world));
+            }
+        }
+        if world.tick >= world.config.ticks {
+            break;
+        }
+        match server.as_mut() {
+            Some(server) => {
+                let plans = server.plans(&world);
+                world.step_with(&plans);
+            }
+            None => world.step(),
+        }
+        world.autosave();
+    }
+    // without a window there's no key to save with, so a run with snapshots ends with one
+    let (save, every) = (&world.config.save, world.config.save_every);
+    let autosaved = every.is_some_and(|every| world.tick.is_multiple_of(every));
+    if (save.is_some() || every.is_some()) && !autosaved {
+        world.save();
+    }
+    if let Some(recorder) = recorder {
+        recorder.finish()?;
+    }
+    let report = world.report();
+    info!("{} ticks, total score {}", report.ticks, report.total_score);
+    if let Some(path) = &world.config.metrics {
+        report.save(path)?;
+    }
+    if let Some(path) = &world.config.heatmap {
+        world.heatmap.save(path, world.config.heatmap_of)?;
+    }
+    Ok(())
+}
@@ -26,10 +26,10 @@ impl Node {
         }
     }
 }
+// nodes are the same if they are for the same location, hash has to agree with eq
 impl Hash for Node {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.location.hash(state);
-        self.fscore.hash(state);
     }
 }
 
@@ -41,7 +41,7 @@ impl Ord for Node {
 
 impl PartialOrd for Node {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.fscore.cmp(&other.fscore))
+        Some(self.cmp(other))
     }
 }
 
@@ -51,7 +51,21 @@ impl PartialEq for Node {
     }
 }
 
-pub(crate) fn astar(grid: &Grid, from: Location, to: Location) -> Option<Vec<Direction>> {
+#[derive(Debug, Default)]
+pub(crate) struct Search {
+    pub path: Option<Vec<Direction>>,
+    /// cells taken off the open list, in the order they were expanded
+    pub expanded: Vec<Location>,
+}
+
+/// The shortest path around everything on `grid` except the cells in `through`.
+pub(crate) fn astar(
+    grid: &Grid,
+    from: Location,
+    to: Location,
+    through: &HashSet<Location>,
+) -> Search {
+    let mut expanded = Vec::new();
     let mut open_list: PriorityQueue<Node, Reverse<u16>> = PriorityQueue::new();
     let mut closed_list: HashSet<Location> = HashSet::new();
     let from_node = Node::new(from, 0, Vec::new());
@@ -60,9 +74,13 @@ pub(crate) fn astar(grid: &Grid, from: Location, to: Location) -> Option<Vec<Dir
         // this should be the most promising path to the destination
         let cur_node = &current_node.0;
         let cur_location = cur_node.location;
+        expanded.push(cur_location);
         if cur_location == to {
             // if the cur_location is the destination, we're guaranteed to have found the /best/ path
-            return Some(cur_node.path.clone());
+            return Search {
+                path: Some(cur_node.path.clone()),
+                expanded,
+            };
         }
         closed_list.insert(cur_location);
         for d in [
@@ -71,42 +89,50 @@ pub(crate) fn astar(grid: &Grid, from: Location, to: Location) -> Option<Vec<Dir
             Direction::Left,
             Direction::Right,
         ] {
-            if cur_location.is_valid_move(d) {
+            if grid.is_valid_move(cur_location, d) {
                 let next_location = cur_location.next_location(d);
-                if next_location == to || grid.is_free(next_location) {
+                if next_location == to
+                    || grid.is_free(next_location)
+                    || through.contains(&next_location)
+                {
                     let h = next_location.distance(to);
                     let g = cur_node.path.len() as u16 + 1;
                     let mut new_path = cur_node.path.clone();
                     new_path.push(d);
                     let child = Node::new(next_location, g + h, new_path);
                     if !closed_list.contains(&next_location)
-                        && !open_list
-                            .iter()
-                            .any(|n| n.0.location == child.location && n.0.fscore < child.fscore)
+                        && open_list
+                            .get(&child)
+                            .is_none_or(|(n, _)| child.fscore < n.fscore)
                     {
-                        // this is now the best way to reach next location
+                        // this is now the best way to reach next location, replace the old node
+                        // as push would only update its priority and keep the old path
+                        open_list.remove(&child);
                         open_list.push(child, Reverse(g + h));
                     }
                 }
             }
         }
     }
-    None
+    Search {
+        path: None,
+        expanded,
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::COLS;
+    use crate::{COLS, ROWS};
 
     use super::*;
     use log::debug;
 
     #[test]
     fn test_path() {
-        let grid = Grid::new();
+        let grid = Grid::new(COLS, ROWS);
         let from = Location::new(0, 0);
         let to = Location::new(1, 1);
-        let path = astar(&grid, from, to);
+        let path = astar(&grid, from, to, &HashSet::new()).path;
         let p = path.unwrap();
         assert_eq!(p.len(), 2);
         assert_eq!(p[0], Direction::Down);
@@ -115,10 +141,10 @@ mod tests {
 
     #[test]
     fn test_path2() {
-        let grid = Grid::new();
+        let grid = Grid::new(COLS, ROWS);
         let from = Location::new(0, 0);
         let to = Location::new(0, 1);
-        let path = astar(&grid, from, to);
+        let path = astar(&grid, from, to, &HashSet::new()).path;
         let p = path.unwrap();
         assert_eq!(p.len(), 1);
         assert_eq!(p[0], Direction::Down);
@@ -126,10 +152,10 @@ mod tests {
 
     #[test]
     fn test_path3() {
-        let grid = Grid::new();
+        let grid = Grid::new(COLS, ROWS);
         let from = Location::new(0, 0);
         let to = Location::new(2, 2);
-        let path = astar(&grid, from, to);
+        let path = astar(&grid, from, to, &HashSet::new()).path;
         let p = path.unwrap();
         debug!("{:?}", p);
         assert_eq!(p.len(), 4);
@@ -141,12 +167,12 @@ mod tests {
 
     #[test]
     fn test_path_obstacle() {
-        let mut grid = Grid::new();
+        let mut grid = Grid::new(COLS, ROWS);
         let from = Location::new(0, 0);
         let to = Location::new(1, 1);
         let obst_location = Location { col: 1, row: 0 };
         grid.set(obst_location);
-        let path = astar(&grid, from, to);
+        let path = astar(&grid, from, to, &HashSet::new()).path;
         let p = path.unwrap();
         assert_eq!(p.len(), 2);
         assert_eq!(p[0], Direction::Down);
@@ -155,23 +181,23 @@ mod tests {
 
     #[test]
     fn test_path_obstacle2() {
-        let mut grid = Grid::new();
+        let mut grid = Grid::new(COLS, ROWS);
         let from = Location::new(0, 0);
         let to = Location::new(2, 2);
         // row of obstacles - no way to reach the destination
         for i in 0..COLS {
             grid.set(Location { col: i, row: 1 });
         }
-        let path = astar(&grid, from, to);
+        let path = astar(&grid, from, to, &HashSet::new()).path;
         assert!(path.is_none());
     }
 
     #[test]
     fn test_big_grid() {
-        let grid = Grid::new();
+        let grid = Grid::new(COLS, ROWS);
         let from = Location::new(0, 0);
         let to = Location::new(9, 9);
-        let path = astar(&grid, from, to);
+        let path = astar(&grid, from, to, &HashSet::new()).path;
         assert!(path.is_some());
         let p = path.unwrap();
         assert_eq!(p.len(), 18);
@@ -179,10 +205,21 @@ mod tests {
 
     #[test]
     fn test_can_not_reach() {
-        let grid = Grid::new();
+        let grid = Grid::new(COLS, ROWS);
         let from = Location::new(0, 0);
         let to = Location::new(100, 100); // these are outside of the grid, no way to find a path
-        let path = astar(&grid, from, to);
+        let path = astar(&grid, from, to, &HashSet::new()).path;
         assert!(path.is_none());
     }
+
+    #[test]
+    fn test_expanded() {
+        let grid = Grid::new(COLS, ROWS);
+        let from = Location::new(0, 0);
+        let to = Location::new(0, 3);
+        let search = astar(&grid, from, to, &HashSet::new());
+        assert_eq!(3, search.path.unwrap().len());
+        assert_eq!(Some(&from), search.expanded.first());
+        assert_eq!(Some(&to), search.expanded.last());
+    }
 }
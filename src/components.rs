@@ -0,0 +1,73 @@
+#[cfg(not(target_arch = "wasm32"))]
+use bracket_color::prelude::RGB;
+use serde::{Deserialize, Serialize};
+
+use crate::{agent::AgentId, decay::decayed};
+
+/// How an entity is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Glyph {
+    pub glyph: u16,
+    pub color: (u8, u8, u8),
+}
+
+impl Glyph {
+    pub(crate) fn new(glyph: u16, color: (u8, u8, u8)) -> Self {
+        Self { glyph, color }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn rgb(&self) -> RGB {
+        RGB::named(self.color)
+    }
+}
+
+/// Points an entity is worth, going down over time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Value {
+    pub score: u8,
+    pub spawned: u64,
+    /// ticks to lose a point, 0 for a value that doesn't decay
+    pub interval: u64,
+    /// ticks after which the entity goes away
+    pub lifetime: Option<u64>,
+}
+
+impl Value {
+    /// What it's worth at `tick`.
+    pub(crate) fn at(&self, tick: u64) -> u8 {
+        decayed(self.score, tick.saturating_sub(self.spawned), self.interval)
+    }
+
+    pub(crate) fn expired(&self, tick: u64) -> bool {
+        let age = tick.saturating_sub(self.spawned);
+        let too_old = self.lifetime.is_some_and(|lifetime| age >= lifetime);
+        too_old || (self.interval > 0 && self.at(tick) == 0)
+    }
+
+    /// The same kind of value starting over at `tick`.
+    pub(crate) fn renewed(&self, score: u8, tick: u64) -> Value {
+        Value {
+            score,
+            spawned: tick,
+            ..*self
+        }
+    }
+}
+
+/// Can pick up an entity and carry its value around.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Carrier {
+    pub carrying: Option<Value>,
+    /// the agent it took what it carries from, who can't take it straight back
+    #[serde(default)]
+    pub stolen_from: Option<AgentId>,
+}
+
+/// Carriers pick this up by stepping onto it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Pickup;
+
+/// Takes what a carrier brings in and awards points for it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Receptacle;
@@ -0,0 +1,213 @@
+use std::str::FromStr;
+
+use crate::{
+    agent::{AgentId, Policy},
+    decay::Decay,
+    heatmap::HeatmapKind,
+    message::Comms,
+    schedule::{Schedule, TieBreak},
+    team::Teams,
+    COLS, NUM_AGENTS, NUM_HOLES, NUM_OBSTACLES, NUM_TILES, ROWS,
+};
+
+/// What draws the world when it isn't headless.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Frontend {
+    #[default]
+    Window,
+    /// text with ANSI colors, for terminals without a display like over SSH
+    Tui,
+}
+
+impl FromStr for Frontend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "window" => Ok(Frontend::Window),
+            "tui" => Ok(Frontend::Tui),
+            _ => Err(format!("unknown frontend: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Config {
+    pub cols: u16,
+    pub rows: u16,
+    pub agents: AgentId,
+    pub tiles: usize,
+    pub holes: usize,
+    pub obstacles: usize,
+    pub decay: Decay,
+    pub teams: Teams,
+    pub comms: Comms,
+    pub policy: Policy,
+    pub schedule: Schedule,
+    /// who gets a cell several agents move into at once
+    pub tie_break: TieBreak,
+    /// file to write the event log to as JSON Lines
+    pub events: Option<String>,
+    /// event log to play back instead of running a simulation
+    pub replay: Option<String>,
+    pub seed: Option<u64>,
+    /// snapshot to continue from
+    pub load: Option<String>,
+    /// where snapshots get saved, `S` saves one
+    pub save: Option<String>,
+    /// also save a snapshot every this many ticks
+    pub save_every: Option<u64>,
+    /// run without a window and exit after `ticks`
+    pub headless: bool,
+    pub ticks: u64,
+    pub frontend: Frontend,
+    /// print the world as text every this many ticks of a headless run
+    pub ascii: Option<u64>,
+    /// file to write the metrics of the run to, CSV for `.csv` and JSON otherwise
+    pub metrics: Option<String>,
+    /// address to take external agent controllers on
+    pub listen: Option<String>,
+    /// controllers to wait for before the first tick
+    pub controllers: usize,
+    /// milliseconds to wait for a controller's action
+    pub timeout: u64,
+    /// agent 0 is played from the keyboard
+    pub player: bool,
+    /// file to write the heatmaps of a headless run to, CSV for `.csv` and a PPM image otherwise
+    pub heatmap: Option<String>,
+    /// which heatmap goes in the image
+    pub heatmap_of: HeatmapKind,
+    /// directory to write every tick of a headless run to as a PPM image
+    pub frames: Option<String>,
+    /// file to write a headless run to as an animated GIF
+    pub gif: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            cols: COLS,
+            rows: ROWS,
+            agents: NUM_AGENTS,
+            tiles: NUM_TILES as usize,
+            holes: NUM_HOLES as usize,
+            obstacles: NUM_OBSTACLES as usize,
+            decay: Decay::default(),
+            teams: Teams::default(),
+            comms: Comms::default(),
+            policy: Policy::default(),
+            schedule: Schedule::default(),
+            tie_break: TieBreak::default(),
+            events: None,
+            replay: None,
+            seed: None,
+            load: None,
+            save: None,
+            save_every: None,
+            headless: false,
+            ticks: 1000,
+            frontend: Frontend::default(),
+            ascii: None,
+            metrics: None,
+            listen: None,
+            controllers: 0,
+            timeout: 100,
+            player: false,
+            heatmap: None,
+            heatmap_of: HeatmapKind::default(),
+            frames: None,
+            gif: None,
+        }
+    }
+}
+
+impl Config {
+    pub(crate) fn from_args(mut args: impl Iterator<Item = String>) -> Result<Config, String> {
+        let mut config = Config::default();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+            match arg.as_str() {
+                "--cols" => config.cols = parse(&value()?)?,
+                "--rows" => config.rows = parse(&value()?)?,
+                "--agents" => config.agents = parse(&value()?)?,
+                "--tiles" => config.tiles = parse(&value()?)?,
+                "--holes" => config.holes = parse(&value()?)?,
+                "--obstacles" => config.obstacles = parse(&value()?)?,
+                "--tile-decay" => config.decay.tile_interval = parse(&value()?)?,
+                "--hole-decay" => config.decay.hole_interval = parse(&value()?)?,
+                "--hole-lifetime" => config.decay.hole_lifetime = Some(parse(&value()?)?),
+                "--teams" => config.teams.count = parse(&value()?)?,
+                "--share-claims" => config.teams.share_claims = true,
+                "--sight" => config.teams.sight = Some(parse(&value()?)?),
+                "--share-sight" => config.teams.share_sight = true,
+                "--steal" => config.teams.steal = true,
+                "--make-way" => config.teams.make_way = true,
+                "--messages" => config.comms.enabled = true,
+                "--message-range" => config.comms.range = Some(parse(&value()?)?),
+                "--bandwidth" => config.comms.bandwidth = Some(parse(&value()?)?),
+                "--message-delay" => config.comms.delay = parse(&value()?)?,
+                "--policy" => config.policy = value()?.parse()?,
+                "--schedule" => config.schedule = value()?.parse()?,
+                "--tie-break" => config.tie_break = value()?.parse()?,
+                "--events" => config.events = Some(value()?),
+                "--replay" => config.replay = Some(value()?),
+                "--seed" => config.seed = Some(parse(&value()?)?),
+                "--load" => config.load = Some(value()?),
+                "--save" => config.save = Some(value()?),
+                "--save-every" => config.save_every = Some(parse(&value()?)?),
+                "--headless" => config.headless = true,
+                "--ticks" => config.ticks = parse(&value()?)?,
+                "--frontend" => config.frontend = value()?.parse()?,
+                "--ascii" => config.ascii = Some(parse(&value()?)?),
+                "--metrics" => config.metrics = Some(value()?),
+                "--listen" => config.listen = Some(value()?),
+                "--controllers" => config.controllers = parse(&value()?)?,
+                "--timeout" => config.timeout = parse(&value()?)?,
+                "--player" => config.player = true,
+                "--heatmap" => config.heatmap = Some(value()?),
+                "--heatmap-of" => config.heatmap_of = value()?.parse()?,
+                "--frames" => config.frames = Some(value()?),
+                "--gif" => config.gif = Some(value()?),
+                _ => return Err(format!("unknown argument: {}", arg)),
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks there is room on the grid for everything that gets placed on it.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let cells = self.cols as usize * self.rows as usize;
+        let objects = self.agents as usize + self.tiles + self.holes + self.obstacles;
+        if self.cols == 0 || self.rows == 0 || objects >= cells {
+            return Err(format!(
+                "{} objects don't fit on a {}x{} grid",
+                objects, self.cols, self.rows
+            ));
+        }
+        if self.player && (self.headless || self.agents == 0) {
+            return Err("--player needs a window and at least one agent".to_string());
+        }
+        let controllable = self.agents as usize - self.player as usize;
+        if self.controllers > controllable {
+            return Err(format!(
+                "--controllers {} but only {} agents can be controlled",
+                self.controllers, controllable
+            ));
+        }
+        if self.comms.delay == 0 {
+            return Err("--message-delay has to be at least 1".to_string());
+        }
+        let dumps = self.frames.is_some() || self.gif.is_some() || self.ascii.is_some();
+        if dumps && !self.headless {
+            return Err("--frames, --gif and --ascii only work with --headless".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("not a number: {}", value))
+}
@@ -0,0 +1,186 @@
+use std::{collections::HashMap, time::Instant};
+
+use bracket_lib::prelude::*;
+
+use crate::{
+    agent::{AgentId, AgentState, Plan},
+    ecs::{Entities, Entity},
+    world::World,
+};
+
+/// Columns of the sidebar next to the grid.
+pub(crate) const SIDEBAR: u16 = 30;
+const CHART_HEIGHT: u16 = 5;
+
+/// The sidebar of the running simulation: tick, time, total score, the agents and a chart of the score.
+pub(crate) struct Dashboard {
+    started: Instant,
+    /// total score after every tick
+    history: Vec<u32>,
+}
+
+impl Default for Dashboard {
+    fn default() -> Self {
+        Dashboard {
+            started: Instant::now(),
+            history: Vec::new(),
+        }
+    }
+}
+
+impl Dashboard {
+    pub(crate) fn record(&mut self, world: &World) {
+        self.history.push(total(&world.entities));
+    }
+
+    /// Draws the dashboard, `player` gets marked with `>`.
+    pub(crate) fn render(&self, world: &World, player: Option<AgentId>, ctx: &mut BTerm) {
+        let (x, rows) = (world.config.cols + 3, world.config.rows);
+        let elapsed = self.started.elapsed().as_secs();
+        ctx.print_color(
+            x,
+            0,
+            BLACK,
+            WHITE,
+            format!("Tick {}  {}:{:02}", world.tick, elapsed / 60, elapsed % 60),
+        );
+        ctx.print_color(
+            x,
+            1,
+            BLACK,
+            WHITE,
+            format!("Total score {}", total(&world.entities)),
+        );
+        let teams: Vec<String> = world
+            .team_scores()
+            .iter()
+            .map(|(team, score)| format!("{}:{}", team, score))
+            .collect();
+        if !teams.is_empty() {
+            let mut line = format!("Teams {}", teams.join(" "));
+            line.truncate(SIDEBAR as usize - 3);
+            ctx.print_color(x, 2, BLACK, WHITE, line);
+        }
+        let chart = rows.saturating_sub(17);
+        agents(
+            &world.entities,
+            world.tick,
+            Some(&world.plans),
+            player,
+            (x, 3),
+            chart.saturating_sub(4),
+            ctx,
+        );
+        ctx.print_color(x, chart, BLACK, WHITE, "Score");
+        self.chart(ctx, x, chart + 1);
+    }
+
+    /// Bars of the total score over the whole run, squeezed into the width of the sidebar.
+    fn chart(&self, ctx: &mut BTerm, x: u16, top: u16) {
+        let width = (SIDEBAR - 4) as usize;
+        let Some(&max) = self.history.iter().max() else {
+            return;
+        };
+        let columns = width.min(self.history.len());
+        for column in 0..columns {
+            // the score at the end of the ticks this column stands for
+            let end = (column + 1) * self.history.len() / columns;
+            let score = self.history[end - 1];
+            let height = (score * CHART_HEIGHT as u32).div_ceil(max.max(1)) as u16;
+            for h in 0..height {
+                ctx.set(
+                    x + column as u16,
+                    top + CHART_HEIGHT - 1 - h,
+                    DARK_GREEN,
+                    WHITE,
+                    219,
+                );
+            }
+        }
+    }
+}
+
+fn total(entities: &Entities) -> u32 {
+    entities.agents.iter().map(|(_, a)| a.score).sum()
+}
+
+/// The agents, three lines each, or one each when there isn't room for that in `height` rows,
+/// or as a table of scores, best first, when there isn't even room for that.
+pub(crate) fn agents(
+    entities: &Entities,
+    tick: u64,
+    plans: Option<&HashMap<Entity, Plan>>,
+    player: Option<AgentId>,
+    (x, top): (u16, u16),
+    height: u16,
+    ctx: &mut BTerm,
+) {
+    let count = entities.agents.len();
+    if count > height as usize {
+        return table(entities, (x, top), height, ctx);
+    }
+    let detailed = count * 3 <= height as usize;
+    let mut row = top;
+    for (entity, agent) in entities.agents.iter() {
+        if player == Some(agent.id) {
+            ctx.print_color(x - 2, row, BLACK, WHITE, ">");
+        }
+        let state = match agent.state {
+            AgentState::MoveToTile => "to tile",
+            AgentState::MoveToHole => "to hole",
+        };
+        let carrying = entities
+            .carriers
+            .get(entity)
+            .and_then(|c| c.carrying)
+            .map_or("-".to_string(), |v| v.at(tick).to_string());
+        let target = plans
+            .and_then(|p| p.get(&entity))
+            .and_then(|p| p.target)
+            .map_or("-".to_string(), |t| format!("{},{}", t.col, t.row));
+        let walked = agent.metrics.distance;
+        if detailed {
+            let lines = [
+                format!("Agent {}: {}", agent.id, agent.score),
+                format!(" {}, carrying {}", state, carrying),
+                format!(" target {} walked {}", target, walked),
+            ];
+            for line in lines {
+                ctx.print_color(x, row, agent.color(), WHITE, line);
+                row += 1;
+            }
+        } else {
+            let line = format!(
+                "{}: {} {} c{} d{}",
+                agent.id, agent.score, state, carrying, walked
+            );
+            ctx.print_color(x, row, agent.color(), WHITE, line);
+            row += 1;
+        }
+    }
+}
+
+/// `id:score` of as many agents as fit, three to a row.
+fn table(entities: &Entities, (x, top): (u16, u16), height: u16, ctx: &mut BTerm) {
+    const PER_ROW: usize = 3;
+    const WIDTH: u16 = 9;
+    let mut agents: Vec<_> = entities.agents.iter().map(|(_, a)| a).collect();
+    agents.sort_by_key(|a| std::cmp::Reverse(a.score));
+    // the last row says how many didn't fit
+    let shown = (height.saturating_sub(1) as usize * PER_ROW).min(agents.len());
+    for (i, agent) in agents[..shown].iter().enumerate() {
+        let (row, column) = ((i / PER_ROW) as u16, (i % PER_ROW) as u16);
+        ctx.print_color(
+            x + column * WIDTH,
+            top + row,
+            agent.color(),
+            WHITE,
+            format!("{}:{}", agent.id, agent.score),
+        );
+    }
+    if shown < agents.len() {
+        let row = top + shown.div_ceil(PER_ROW) as u16;
+        let more = format!("+{} more", agents.len() - shown);
+        ctx.print_color(x, row, BLACK, WHITE, more);
+    }
+}
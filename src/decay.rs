@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::Value;
+
+/// How tile and hole values age over time, all durations are in ticks.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Decay {
+    /// ticks for a tile to lose one point, 0 disables tile decay
+    pub tile_interval: u64,
+    /// ticks for a hole to lose one point, 0 disables hole decay
+    pub hole_interval: u64,
+    /// ticks after which a hole closes and opens up somewhere else
+    pub hole_lifetime: Option<u64>,
+}
+
+impl Decay {
+    pub(crate) fn tile(&self, score: u8, tick: u64) -> Value {
+        Value {
+            score,
+            spawned: tick,
+            interval: self.tile_interval,
+            lifetime: None,
+        }
+    }
+
+    /// Deliveries only score what's left of the hole on top of the tile when holes age.
+    pub(crate) fn scores_holes(&self) -> bool {
+        self.hole_interval > 0 || self.hole_lifetime.is_some()
+    }
+
+    pub(crate) fn hole(&self, score: u8, tick: u64) -> Value {
+        Value {
+            score,
+            spawned: tick,
+            interval: self.hole_interval,
+            lifetime: self.hole_lifetime,
+        }
+    }
+}
+
+pub(crate) fn decayed(score: u8, age: u64, interval: u64) -> u8 {
+    if interval == 0 {
+        return score;
+    }
+    let lost = (age / interval).min(u8::MAX as u64) as u8;
+    score.saturating_sub(lost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_decay() {
+        let decay = Decay::default();
+        let tile = decay.tile(4, 0);
+        let hole = decay.hole(4, 0);
+        assert_eq!(4, tile.at(1000));
+        assert_eq!(4, hole.at(1000));
+        assert!(!tile.expired(1000));
+        assert!(!hole.expired(1000));
+        assert!(!decay.scores_holes());
+    }
+
+    #[test]
+    fn test_tile_decay() {
+        let decay = Decay {
+            tile_interval: 10,
+            ..Default::default()
+        };
+        let tile = decay.tile(4, 5);
+        assert_eq!(4, tile.at(14));
+        assert_eq!(3, tile.at(15));
+        assert_eq!(1, tile.at(44));
+        assert!(!tile.expired(44));
+        assert_eq!(0, tile.at(45));
+        assert!(tile.expired(45));
+        assert_eq!(0, tile.at(u64::MAX));
+        // spawned later than asked about, nothing lost yet
+        assert_eq!(4, tile.at(0));
+    }
+
+    #[test]
+    fn test_hole_lifetime() {
+        let decay = Decay {
+            hole_lifetime: Some(50),
+            ..Default::default()
+        };
+        let hole = decay.hole(3, 0);
+        assert!(decay.scores_holes());
+        assert_eq!(3, hole.at(49));
+        assert!(!hole.expired(49));
+        assert!(hole.expired(50));
+    }
+}
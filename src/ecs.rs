@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+
+use bracket_color::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    agent::{Agent, AgentId},
+    components::{Carrier, Glyph, Pickup, Receptacle, Value},
+    decay::Decay,
+    events::{EntityKind, Event},
+    location::Location,
+};
+
+pub(crate) type Entity = usize;
+
+/// One type of component, keyed by the entity it belongs to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct Storage<T>(BTreeMap<Entity, T>);
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Storage(BTreeMap::new())
+    }
+}
+
+impl<T> Storage<T> {
+    pub(crate) fn insert(&mut self, entity: Entity, component: T) {
+        self.0.insert(entity, component);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn remove(&mut self, entity: Entity) -> Option<T> {
+        self.0.remove(&entity)
+    }
+
+    pub(crate) fn get(&self, entity: Entity) -> Option<&T> {
+        self.0.get(&entity)
+    }
+
+    pub(crate) fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.0.get_mut(&entity)
+    }
+
+    pub(crate) fn contains(&self, entity: Entity) -> bool {
+        self.0.contains_key(&entity)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.keys().copied()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.0.iter().map(|(e, c)| (*e, c))
+    }
+}
+
+/// Everything in the world, each entity is made up of the components stored for it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Entities {
+    next: Entity,
+    pub kinds: Storage<EntityKind>,
+    pub positions: Storage<Location>,
+    pub glyphs: Storage<Glyph>,
+    pub values: Storage<Value>,
+    pub carriers: Storage<Carrier>,
+    pub pickups: Storage<Pickup>,
+    pub receptacles: Storage<Receptacle>,
+    pub agents: Storage<Agent>,
+}
+
+impl Entities {
+    pub(crate) fn create(&mut self) -> Entity {
+        let entity = self.next;
+        self.next += 1;
+        entity
+    }
+
+    /// Gives `entity` the components of its kind, agents get their `Agent` from `add_agent`.
+    pub(crate) fn spawn(
+        &mut self,
+        entity: Entity,
+        kind: EntityKind,
+        location: Location,
+        score: u8,
+        tick: u64,
+        decay: &Decay,
+    ) {
+        self.next = self.next.max(entity + 1);
+        self.kinds.insert(entity, kind);
+        self.positions.insert(entity, location);
+        match kind {
+            EntityKind::Agent => {
+                self.carriers.insert(entity, Carrier::default());
+            }
+            EntityKind::Tile => {
+                self.glyphs.insert(entity, Glyph::new(7, BLACK));
+                self.values.insert(entity, decay.tile(score, tick));
+                self.pickups.insert(entity, Pickup);
+            }
+            EntityKind::Hole => {
+                self.glyphs.insert(entity, Glyph::new(9, BLACK));
+                self.values.insert(entity, decay.hole(score, tick));
+                self.receptacles.insert(entity, Receptacle);
+            }
+            EntityKind::Obstacle => {
+                self.glyphs.insert(entity, Glyph::new(178, BLACK));
+            }
+        }
+    }
+
+    /// Takes every component of `entity` away.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn despawn(&mut self, entity: Entity) {
+        self.kinds.remove(entity);
+        self.positions.remove(entity);
+        self.glyphs.remove(entity);
+        self.values.remove(entity);
+        self.carriers.remove(entity);
+        self.pickups.remove(entity);
+        self.receptacles.remove(entity);
+        self.agents.remove(entity);
+    }
+
+    pub(crate) fn add_agent(&mut self, entity: Entity, agent: Agent) {
+        self.glyphs.insert(entity, agent.glyph());
+        self.agents.insert(entity, agent);
+    }
+
+    /// The entity of the agent with `id`.
+    pub(crate) fn agent(&self, id: AgentId) -> Option<Entity> {
+        self.agents.iter().find(|(_, a)| a.id == id).map(|(e, _)| e)
+    }
+
+    pub(crate) fn at(&self, location: Location) -> Option<Entity> {
+        self.positions
+            .iter()
+            .find(|(_, l)| **l == location)
+            .map(|(e, _)| e)
+    }
+
+    /// Entities that have a component in `storage` along with where they are.
+    pub(crate) fn located<'a, T>(
+        &'a self,
+        storage: &'a Storage<T>,
+    ) -> impl Iterator<Item = (Entity, Location)> + 'a {
+        storage
+            .entities()
+            .filter_map(|e| self.positions.get(e).map(|l| (e, *l)))
+    }
+
+    pub(crate) fn spawned_event(&self, entity: Entity) -> Option<Event> {
+        Some(Event::EntitySpawned {
+            kind: *self.kinds.get(entity)?,
+            id: entity,
+            location: *self.positions.get(entity)?,
+            score: self.values.get(entity).map(|v| v.score),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn() {
+        let mut entities = Entities::default();
+        let decay = Decay::default();
+        let tile = entities.create();
+        entities.spawn(tile, EntityKind::Tile, Location::new(1, 2), 3, 0, &decay);
+        let hole = entities.create();
+        entities.spawn(hole, EntityKind::Hole, Location::new(4, 4), 2, 0, &decay);
+        assert_ne!(tile, hole);
+        assert!(entities.pickups.contains(tile));
+        assert!(!entities.pickups.contains(hole));
+        assert!(entities.receptacles.contains(hole));
+        assert_eq!(Some(tile), entities.at(Location::new(1, 2)));
+        assert_eq!(None, entities.at(Location::new(0, 0)));
+        let located: Vec<_> = entities.located(&entities.values).collect();
+        assert_eq!(
+            vec![(tile, Location::new(1, 2)), (hole, Location::new(4, 4))],
+            located
+        );
+        // spawning with a given id keeps new ids clear of it
+        entities.spawn(10, EntityKind::Obstacle, Location::new(5, 5), 0, 0, &decay);
+        assert_eq!(11, entities.create());
+    }
+}
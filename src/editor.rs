@@ -0,0 +1,175 @@
+use bracket_lib::prelude::*;
+
+use crate::{ecs::Entity, events::EntityKind, location::Location, world::World};
+
+const LEFT: usize = 0;
+const RIGHT: usize = 1;
+
+/// Changes the world with the mouse while it runs: left click places the selected kind on a free cell
+/// or picks up an agent to drag it, right click removes what's there. 1, 2 and 3 select obstacles,
+/// tiles and holes.
+pub(crate) struct Editor {
+    tool: EntityKind,
+    dragging: Option<Entity>,
+    held: [bool; 2],
+    hovered: Option<Location>,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Editor {
+            tool: EntityKind::Obstacle,
+            dragging: None,
+            held: [false; 2],
+            hovered: None,
+        }
+    }
+}
+
+impl Editor {
+    /// Returns whether `key` selected a tool.
+    pub(crate) fn handle_key(&mut self, key: VirtualKeyCode) -> bool {
+        self.tool = match key {
+            VirtualKeyCode::Key1 => EntityKind::Obstacle,
+            VirtualKeyCode::Key2 => EntityKind::Tile,
+            VirtualKeyCode::Key3 => EntityKind::Hole,
+            _ => return false,
+        };
+        true
+    }
+
+    /// Applies what the mouse did since the last frame.
+    pub(crate) fn update(&mut self, ctx: &BTerm, world: &mut World) {
+        let (col, row) = ctx.mouse_pos();
+        let (cols, rows) = (world.config.cols as i32, world.config.rows as i32);
+        self.hovered = ((0..cols).contains(&col) && (0..rows).contains(&row))
+            .then(|| Location::new(col as u16, row as u16));
+        let input = INPUT.lock();
+        let now = [
+            input.is_mouse_button_pressed(LEFT),
+            input.is_mouse_button_pressed(RIGHT),
+        ];
+        drop(input);
+        let pressed = |button: usize| now[button] && !self.held[button];
+        match self.hovered {
+            Some(location) if pressed(LEFT) => self.press(world, location),
+            Some(location) if pressed(RIGHT) => self.erase(world, location),
+            Some(location) if now[LEFT] => self.drag(world, location),
+            _ => {}
+        }
+        if !now[LEFT] {
+            self.dragging = None;
+        }
+        self.held = now;
+    }
+
+    fn press(&mut self, world: &mut World, location: Location) {
+        match world.entities.at(location) {
+            Some(entity) if world.entities.agents.contains(entity) => self.dragging = Some(entity),
+            Some(_) => {}
+            None => {
+                world.place(self.tool, location);
+            }
+        }
+    }
+
+    fn drag(&mut self, world: &mut World, location: Location) {
+        if let Some(entity) = self.dragging {
+            if world.entities.positions.get(entity) != Some(&location) {
+                world.relocate(entity, location);
+            }
+        }
+    }
+
+    fn erase(&mut self, world: &mut World, location: Location) {
+        if let Some(entity) = world.entities.at(location) {
+            world.remove(entity);
+        }
+    }
+
+    /// Shows the selected tool and what's in the cell under the mouse at the bottom of the sidebar.
+    pub(crate) fn render(&self, world: &World, ctx: &mut BTerm) {
+        let (cols, rows) = (world.config.cols, world.config.rows);
+        ctx.print_color(
+            cols + 3,
+            rows.saturating_sub(4),
+            BLACK,
+            WHITE,
+            format!("place: {:?}", self.tool),
+        );
+        let Some(location) = self.hovered else {
+            return;
+        };
+        ctx.print_color(
+            cols + 3,
+            rows.saturating_sub(3),
+            BLACK,
+            WHITE,
+            format!("at {},{}:", location.col, location.row),
+        );
+        ctx.print_color(
+            cols + 3,
+            rows.saturating_sub(2),
+            BLACK,
+            WHITE,
+            describe(world, location),
+        );
+    }
+}
+
+/// What's in the cell at `location`, in a few words.
+fn describe(world: &World, location: Location) -> String {
+    let entities = &world.entities;
+    let Some(entity) = entities.at(location) else {
+        return "nothing".to_string();
+    };
+    if let Some(agent) = entities.agents.get(entity) {
+        let carrying = entities.carriers.get(entity).and_then(|c| c.carrying);
+        return match carrying {
+            Some(value) => format!("agent {} with {}", agent.id, value.at(world.tick)),
+            None => format!("agent {}", agent.id),
+        };
+    }
+    let kind = entities
+        .kinds
+        .get(entity)
+        .map_or("?".to_string(), |k| format!("{:?}", k).to_lowercase());
+    match entities.values.get(entity) {
+        Some(value) => format!("{} worth {}", kind, value.at(world.tick)),
+        None => kind,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::tests::empty_world;
+
+    #[test]
+    fn test_edit() {
+        let mut world = empty_world();
+        let mut editor = Editor::default();
+        editor.handle_key(VirtualKeyCode::Key2);
+        let free = world.grid.random_location(&mut world.rng);
+        editor.press(&mut world, free);
+        let tile = world.entities.at(free).unwrap();
+        assert!(world.entities.pickups.contains(tile));
+        assert!(!world.grid.is_free(free));
+        assert!(describe(&world, free).starts_with("tile worth"));
+
+        // agents can't be removed, only dragged to a free cell
+        let agent = *world.entities.positions.get(0).unwrap();
+        editor.erase(&mut world, agent);
+        assert!(world.entities.agents.contains(0));
+        editor.press(&mut world, agent);
+        editor.drag(&mut world, free);
+        assert_eq!(Some(&agent), world.entities.positions.get(0));
+        editor.erase(&mut world, free);
+        assert!(!world.entities.kinds.contains(tile));
+        assert!(world.grid.is_free(free));
+        editor.drag(&mut world, free);
+        assert_eq!(Some(&free), world.entities.positions.get(0));
+        assert!(world.grid.is_free(agent));
+        assert_eq!("agent 0", describe(&world, free));
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use log::warn;
+use rand::Rng;
+
+use crate::{
+    agent::{Action, AgentId, Plan},
+    config::Config,
+    events::{EntityKind, EventLog},
+    location::{Direction, Location},
+    message::Outgoing,
+    world::World,
+};
+
+/// What the action indices passed to `Env::step` stand for.
+const ACTIONS: [Action; 5] = [
+    Action::Wait,
+    Action::Move(Direction::Up),
+    Action::Move(Direction::Down),
+    Action::Move(Direction::Left),
+    Action::Move(Direction::Right),
+];
+
+/// Channels of an `Observation` for each kind of entity, in order, the carrying channel comes after them.
+const CHANNELS: [EntityKind; 4] = [
+    EntityKind::Agent,
+    EntityKind::Tile,
+    EntityKind::Hole,
+    EntityKind::Obstacle,
+];
+
+/// Marks the agents carrying a tile.
+const CARRYING: usize = CHANNELS.len();
+
+/// The grid as a `channels x rows x cols` tensor, one channel each for agents, tiles, holes, obstacles and
+/// carried tiles. Agents are marked with their id + 1, tiles and holes with what they're worth now, obstacles
+/// with 1 and agents carrying a tile with what it's worth now, at least 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    pub channels: usize,
+    pub rows: usize,
+    pub cols: usize,
+    pub data: Vec<f32>,
+    /// what each agent, by id, got told to read next tick, as JSON like `{"from":1,"to":null,"sent":4,
+    /// "content":{"Tile":{"col":2,"row":7}}}`
+    pub messages: Vec<Vec<String>>,
+}
+
+impl Observation {
+    pub fn get(&self, channel: usize, row: usize, col: usize) -> f32 {
+        self.data[(channel * self.rows + row) * self.cols + col]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Info {
+    pub tick: u64,
+    pub scores: Vec<u32>,
+    /// the episode ended because it hit the tick limit
+    pub truncated: bool,
+}
+
+/// What came of one `Env::step`, rewards are the points each agent scored in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub observation: Observation,
+    pub rewards: Vec<f32>,
+    pub done: bool,
+    pub info: Info,
+}
+
+/// Tileworld as a reinforcement learning environment, every agent is controlled from outside.
+/// Episodes last `--ticks` ticks.
+pub struct Env {
+    config: Config,
+    world: World,
+    /// messages agents send with their next action
+    outbox: HashMap<AgentId, Vec<Outgoing>>,
+}
+
+impl Env {
+    /// Takes the same options as the command line.
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<Env, String> {
+        let config = Config::from_args(args)?;
+        let world = World::new(config.clone(), EventLog::recording());
+        Ok(Env {
+            config,
+            world,
+            outbox: HashMap::new(),
+        })
+    }
+
+    /// Actions per agent are indices into wait, up, down, left and right.
+    pub fn actions(&self) -> usize {
+        ACTIONS.len()
+    }
+
+    pub fn agents(&self) -> usize {
+        self.world.entities.agents.len()
+    }
+
+    /// Starts a new episode, without a seed it is drawn from the previous episode.
+    pub fn reset(&mut self, seed: Option<u64>) -> Observation {
+        self.config.seed = Some(seed.unwrap_or_else(|| self.world.rng.gen()));
+        self.world = World::new(self.config.clone(), EventLog::recording());
+        self.outbox.clear();
+        self.observe()
+    }
+
+    /// Has `agent` send `message` in the next step, JSON like `{"to":1,"content":{"Claim":{"col":2,"row":7}}}`
+    /// or without `to` to everyone in range.
+    pub fn send(&mut self, agent: usize, message: &str) -> Result<(), String> {
+        let message = serde_json::from_str(message).map_err(|e| e.to_string())?;
+        let agent = AgentId::try_from(agent).map_err(|e| e.to_string())?;
+        self.outbox.entry(agent).or_default().push(message);
+        Ok(())
+    }
+
+    /// Advances one tick with `actions[i]` for agent i, agents without an action wait.
+    /// Once the episode is done it has to be `reset` first.
+    pub fn step(&mut self, actions: &[usize]) -> Result<Step, String> {
+        if self.world.tick >= self.config.ticks {
+            return Err("the episode is done, call reset first".to_string());
+        }
+        self.world.events.take();
+        let before = self.scores();
+        let outbox = std::mem::take(&mut self.outbox);
+        self.world.advance(|world, entity| {
+            let agent = world.entities.agents.get(entity)?;
+            let index = actions.get(agent.id as usize).copied().unwrap_or(0);
+            let action = ACTIONS.get(index).copied().unwrap_or_else(|| {
+                warn!("Agent {}: unknown action {}", agent.id, index);
+                Action::Wait
+            });
+            Some(Plan {
+                action,
+                messages: outbox.get(&agent.id).cloned().unwrap_or_default(),
+                ..Default::default()
+            })
+        });
+        let scores = self.scores();
+        let rewards = scores
+            .iter()
+            .zip(before.iter())
+            .map(|(after, before)| (after - before) as f32)
+            .collect();
+        let done = self.world.tick >= self.config.ticks;
+        Ok(Step {
+            observation: self.observe(),
+            rewards,
+            done,
+            info: Info {
+                tick: self.world.tick,
+                scores,
+                truncated: done,
+            },
+        })
+    }
+
+    pub fn observe(&self) -> Observation {
+        let entities = &self.world.entities;
+        let (rows, cols) = (self.config.rows as usize, self.config.cols as usize);
+        let channels = CHANNELS.len() + 1;
+        let mut data = vec![0.0; channels * rows * cols];
+        let mut mark = |channel: usize, location: Location, value: f32| {
+            data[(channel * rows + location.row as usize) * cols + location.col as usize] = value;
+        };
+        for (entity, kind) in entities.kinds.iter() {
+            let (Some(channel), Some(location)) = (
+                CHANNELS.iter().position(|k| k == kind),
+                entities.positions.get(entity),
+            ) else {
+                continue;
+            };
+            let value = match (entities.agents.get(entity), entities.values.get(entity)) {
+                (Some(agent), _) => agent.id as f32 + 1.0,
+                (_, Some(value)) => value.at(self.world.tick) as f32,
+                _ => 1.0,
+            };
+            mark(channel, *location, value);
+            let carried = entities.carriers.get(entity).and_then(|c| c.carrying);
+            if let Some(carried) = carried {
+                mark(
+                    CARRYING,
+                    *location,
+                    carried.at(self.world.tick).max(1) as f32,
+                );
+            }
+        }
+        let mut messages = vec![Vec::new(); self.agents()];
+        for (_, agent) in entities.agents.iter() {
+            if let Some(inbox) = messages.get_mut(agent.id as usize) {
+                let read = self.world.channel.inbox(agent.id).iter();
+                *inbox = read.filter_map(|m| serde_json::to_string(m).ok()).collect();
+            }
+        }
+        Observation {
+            channels,
+            rows,
+            cols,
+            data,
+            messages,
+        }
+    }
+
+    /// What happened in the last step, or since the reset, as JSON like the `--events` log.
+    pub fn events(&self) -> Vec<String> {
+        let records = self.world.events.recorded().iter();
+        records
+            .filter_map(|r| serde_json::to_string(r).ok())
+            .collect()
+    }
+
+    /// Scores by agent id.
+    fn scores(&self) -> Vec<u32> {
+        let mut scores = vec![0; self.agents()];
+        for (_, agent) in self.world.entities.agents.iter() {
+            if let Some(score) = scores.get_mut(agent.id as usize) {
+                *score = agent.score;
+            }
+        }
+        scores
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env() -> Env {
+        let args = ["--ticks", "20", "--agents", "2"];
+        Env::from_args(args.iter().map(|a| a.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut env = env();
+        let observation = env.reset(Some(3));
+        assert_eq!(observation, env.reset(Some(3)));
+        assert_eq!(5 * 40 * 40, observation.data.len());
+        let agents = observation.data[..40 * 40].iter().filter(|v| **v > 0.0);
+        assert_eq!(2, agents.count());
+        let obstacles = observation.data[3 * 40 * 40..].iter().sum::<f32>();
+        assert_eq!(20.0, obstacles);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut env = env();
+        env.reset(Some(3));
+        let mut total = 0.0;
+        for tick in 1..=20 {
+            let step = env.step(&[1, 4]).unwrap();
+            assert_eq!(tick == 20, step.done);
+            assert_eq!(tick, step.info.tick);
+            total += step.rewards.iter().sum::<f32>();
+            let scores = step.info.scores.iter().sum::<u32>();
+            assert_eq!(scores as f32, total);
+            assert!(step.rewards.iter().all(|r| *r == 0.0) || !env.events().is_empty());
+        }
+        assert!(env.step(&[0, 0]).is_err());
+        env.reset(None);
+        assert!(env.step(&[0, 0]).is_ok());
+    }
+
+    #[test]
+    fn test_carrying() {
+        let mut env = env();
+        let observation = env.reset(Some(3));
+        assert!(observation.data[4 * 40 * 40..].iter().all(|v| *v == 0.0));
+        let entity = env.world.entities.agent(0).unwrap();
+        let location = *env.world.entities.positions.get(entity).unwrap();
+        let value = env.world.config.decay.tile(3, 0);
+        env.world
+            .entities
+            .carriers
+            .get_mut(entity)
+            .unwrap()
+            .carrying = Some(value);
+        let observation = env.observe();
+        let (row, col) = (location.row as usize, location.col as usize);
+        assert_eq!(3.0, observation.get(4, row, col));
+    }
+
+    #[test]
+    fn test_messages() {
+        let mut env = env();
+        let observation = env.reset(Some(3));
+        assert_eq!(vec![Vec::<String>::new(); 2], observation.messages);
+        let message = r#"{"content":{"Tile":{"col":2,"row":7}}}"#;
+        env.send(0, message).unwrap();
+        assert!(env.send(0, "{}").is_err());
+        let step = env.step(&[0, 0]).unwrap();
+        assert!(step.observation.messages[0].is_empty());
+        assert_eq!(
+            vec![r#"{"from":0,"to":null,"sent":1,"content":{"Tile":{"col":2,"row":7}}}"#],
+            step.observation.messages[1]
+        );
+        let step = env.step(&[0, 0]).unwrap();
+        assert!(step.observation.messages[1].is_empty());
+    }
+}
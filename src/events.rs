@@ -0,0 +1,180 @@
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{agent::AgentId, decay::Decay, location::Location};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum EntityKind {
+    Agent,
+    Tile,
+    Hole,
+    Obstacle,
+}
+
+/// Everything that can happen in a run, `id` is the entity.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub(crate) enum Event {
+    WorldCreated {
+        cols: u16,
+        rows: u16,
+        /// how tile and hole values age, for replays
+        #[serde(default)]
+        decay: Decay,
+    },
+    EntitySpawned {
+        kind: EntityKind,
+        id: usize,
+        location: Location,
+        score: Option<u8>,
+    },
+    EntityExpired {
+        kind: EntityKind,
+        id: usize,
+        location: Location,
+    },
+    /// taken out of the world for good, by editing it
+    EntityRemoved {
+        kind: EntityKind,
+        id: usize,
+        location: Location,
+    },
+    AgentMoved {
+        agent: AgentId,
+        from: Location,
+        to: Location,
+    },
+    TilePickedUp {
+        agent: AgentId,
+        tile: usize,
+        location: Location,
+        value: u8,
+    },
+    TileDropped {
+        agent: AgentId,
+        location: Location,
+        value: u8,
+    },
+    /// `agent` took the tile agent `from` carried
+    TileStolen {
+        agent: AgentId,
+        from: AgentId,
+        location: Location,
+        value: u8,
+    },
+    HoleFilled {
+        agent: AgentId,
+        hole: usize,
+        location: Location,
+        points: u32,
+    },
+    PathNotFound {
+        agent: AgentId,
+        from: Location,
+        to: Location,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Record {
+    pub tick: u64,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+/// Stamps events with the current tick and optionally streams them out as JSON Lines or keeps them.
+#[derive(Default)]
+pub(crate) struct EventLog {
+    tick: u64,
+    sink: Option<Box<dyn Write + Send>>,
+    recorded: Option<Vec<Record>>,
+}
+
+impl EventLog {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn to_writer(sink: impl Write + Send + 'static) -> EventLog {
+        EventLog {
+            sink: Some(Box::new(sink)),
+            ..Default::default()
+        }
+    }
+
+    /// Keeps events in memory until they're taken with `take`.
+    pub(crate) fn recording() -> EventLog {
+        EventLog {
+            recorded: Some(Vec::new()),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn recorded(&self) -> &[Record] {
+        self.recorded.as_deref().unwrap_or_default()
+    }
+
+    pub(crate) fn take(&mut self) -> Vec<Record> {
+        self.recorded
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn begin_tick(&mut self, tick: u64) {
+        self.tick = tick;
+    }
+
+    pub(crate) fn emit(&mut self, event: Event) {
+        let record = Record {
+            tick: self.tick,
+            event,
+        };
+        if let Some(sink) = self.sink.as_mut() {
+            if let Err(e) = write_record(sink, &record) {
+                log::error!("failed to write event, disabling event log: {}", e);
+                self.sink = None;
+            }
+        }
+        if let Some(recorded) = self.recorded.as_mut() {
+            recorded.push(record);
+        }
+    }
+
+    pub(crate) fn flush(&mut self) {
+        if let Some(sink) = self.sink.as_mut() {
+            if let Err(e) = sink.flush() {
+                log::error!("failed to flush event log: {}", e);
+            }
+        }
+    }
+}
+
+fn write_record(sink: &mut impl Write, record: &Record) -> io::Result<()> {
+    serde_json::to_writer(&mut *sink, record)?;
+    sink.write_all(b"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_lines() {
+        let record = Record {
+            tick: 3,
+            event: Event::AgentMoved {
+                agent: 1,
+                from: Location::new(0, 0),
+                to: Location::new(0, 1),
+            },
+        };
+        let mut out = Vec::new();
+        write_record(&mut out, &record).unwrap();
+        let line = String::from_utf8(out).unwrap();
+        assert_eq!(
+            "{\"tick\":3,\"type\":\"AgentMoved\",\"agent\":1,\"from\":{\"col\":0,\"row\":0},\"to\":{\"col\":0,\"row\":1}}\n",
+            line
+        );
+        let parsed: Record = serde_json::from_str(line.trim_end()).unwrap();
+        assert_eq!(record, parsed);
+    }
+}
@@ -0,0 +1,318 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use log::info;
+use serde::Deserialize;
+
+use crate::{
+    agent::{AgentId, Policy},
+    config::Config,
+    decay::Decay,
+    events::EventLog,
+    message::Comms,
+    metrics::RunReport,
+    team::Teams,
+    world::World,
+    COLS, NUM_AGENTS, ROWS,
+};
+
+/// Two sided 95% critical values of Student's t for 1 to 30 degrees of freedom.
+const T_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+
+const METRICS: [&str; 8] = [
+    "total_score",
+    "tiles_delivered",
+    "tiles_stolen",
+    "messages_sent",
+    "average_delivery_ticks",
+    "idle_ticks",
+    "failed_plans",
+    "planning_ms",
+];
+
+/// What to run, every combination of seed, grid size, agent count and policy is one run.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub(crate) struct Sweep {
+    pub seeds: Vec<u64>,
+    pub grid_sizes: Vec<(u16, u16)>,
+    pub agents: Vec<AgentId>,
+    pub policies: Vec<String>,
+    pub ticks: u64,
+    pub decay: Decay,
+    pub teams: Teams,
+    pub comms: Comms,
+    /// defaults to the number of cores
+    pub threads: Option<usize>,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Sweep {
+            seeds: (0..10).collect(),
+            grid_sizes: vec![(COLS, ROWS)],
+            agents: vec![NUM_AGENTS],
+            policies: vec!["closest".to_string()],
+            ticks: 1000,
+            decay: Decay::default(),
+            teams: Teams::default(),
+            comms: Comms::default(),
+            threads: None,
+        }
+    }
+}
+
+/// The settings runs are grouped by when aggregating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Setting {
+    cols: u16,
+    rows: u16,
+    agents: AgentId,
+    policy: Policy,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    setting: Setting,
+    seed: u64,
+}
+
+impl Sweep {
+    fn runs(&self) -> Result<Vec<Run>, String> {
+        let policies = self
+            .policies
+            .iter()
+            .map(|p| p.parse())
+            .collect::<Result<Vec<Policy>, String>>()?;
+        let mut runs = Vec::new();
+        for &(cols, rows) in self.grid_sizes.iter() {
+            for &agents in self.agents.iter() {
+                for &policy in policies.iter() {
+                    for &seed in self.seeds.iter() {
+                        let setting = Setting {
+                            cols,
+                            rows,
+                            agents,
+                            policy,
+                        };
+                        let run = Run { setting, seed };
+                        self.config(&run).validate()?;
+                        runs.push(run);
+                    }
+                }
+            }
+        }
+        Ok(runs)
+    }
+
+    fn config(&self, run: &Run) -> Config {
+        Config {
+            cols: run.setting.cols,
+            rows: run.setting.rows,
+            agents: run.setting.agents,
+            policy: run.setting.policy,
+            decay: self.decay,
+            teams: self.teams,
+            comms: self.comms,
+            seed: Some(run.seed),
+            headless: true,
+            ticks: self.ticks,
+            ..Default::default()
+        }
+    }
+
+    fn execute(&self, run: &Run) -> RunReport {
+        let mut world = World::new(self.config(run), EventLog::default());
+        while world.tick < self.ticks {
+            world.step();
+        }
+        world.report()
+    }
+
+    /// Runs everything on a pool of threads, the reports are in the order of `runs`.
+    fn execute_all(&self, runs: &[Run]) -> Vec<RunReport> {
+        let threads = self
+            .threads
+            .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+            .unwrap_or(1)
+            .clamp(1, runs.len().max(1));
+        let next = AtomicUsize::new(0);
+        let reports = Mutex::new(Vec::with_capacity(runs.len()));
+        thread::scope(|s| {
+            for _ in 0..threads {
+                s.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(run) = runs.get(index) else {
+                        break;
+                    };
+                    let report = self.execute(run);
+                    info!(
+                        "{:?} seed {}: {}",
+                        run.setting, run.seed, report.total_score
+                    );
+                    reports.lock().unwrap().push((index, report));
+                });
+            }
+        });
+        let mut reports = reports.into_inner().unwrap();
+        reports.sort_by_key(|(index, _)| *index);
+        reports.into_iter().map(|(_, report)| report).collect()
+    }
+}
+
+fn measure(report: &RunReport) -> [f64; METRICS.len()] {
+    let agents = &report.agents;
+    let sum = |f: fn(&crate::metrics::AgentReport) -> f64| agents.iter().map(f).sum::<f64>();
+    let delivered = sum(|a| a.tiles_delivered as f64);
+    let delivery_ticks = sum(|a| a.average_delivery_ticks * a.tiles_delivered as f64);
+    [
+        report.total_score as f64,
+        delivered,
+        sum(|a| a.tiles_stolen as f64),
+        sum(|a| a.messages_sent as f64),
+        if delivered > 0.0 {
+            delivery_ticks / delivered
+        } else {
+            0.0
+        },
+        sum(|a| a.idle_ticks as f64),
+        sum(|a| a.failed_plans as f64),
+        sum(|a| a.planning_ms),
+    ]
+}
+
+/// Mean and half width of the 95% confidence interval.
+fn mean_ci(values: &[f64]) -> (f64, f64) {
+    let n = values.len();
+    if n == 0 {
+        return (0.0, 0.0);
+    }
+    let mean = values.iter().sum::<f64>() / n as f64;
+    if n == 1 {
+        return (mean, 0.0);
+    }
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+    let t = T_95.get(n - 2).copied().unwrap_or(1.96);
+    (mean, t * (variance / n as f64).sqrt())
+}
+
+fn write_table(out: &mut impl Write, runs: &[Run], reports: &[RunReport]) -> io::Result<()> {
+    write!(out, "cols,rows,agents,policy,runs")?;
+    for name in METRICS {
+        write!(out, ",{}_mean,{}_ci95", name, name)?;
+    }
+    writeln!(out)?;
+    let mut settings: Vec<Setting> = Vec::new();
+    for run in runs.iter() {
+        if !settings.contains(&run.setting) {
+            settings.push(run.setting);
+        }
+    }
+    for setting in settings {
+        let measured: Vec<_> = runs
+            .iter()
+            .zip(reports.iter())
+            .filter(|(run, _)| run.setting == setting)
+            .map(|(_, report)| measure(report))
+            .collect();
+        write!(
+            out,
+            "{},{},{},{:?},{}",
+            setting.cols,
+            setting.rows,
+            setting.agents,
+            setting.policy,
+            measured.len()
+        )?;
+        for i in 0..METRICS.len() {
+            let values: Vec<f64> = measured.iter().map(|m| m[i]).collect();
+            let (mean, ci) = mean_ci(&values);
+            write!(out, ",{:.3},{:.3}", mean, ci)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// `experiment SWEEP.json [--out FILE]`, writes the aggregated table to FILE or stdout.
+pub(crate) fn run(
+    mut args: impl Iterator<Item = String>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut spec = None;
+    let mut out = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => out = Some(args.next().ok_or("missing value for --out")?),
+            _ if spec.is_none() => spec = Some(arg),
+            _ => return Err(format!("unknown argument: {}", arg).into()),
+        }
+    }
+    let spec = spec.ok_or("usage: experiment SWEEP.json [--out FILE]")?;
+    let sweep: Sweep = serde_json::from_reader(BufReader::new(File::open(spec)?))?;
+    let runs = sweep.runs()?;
+    info!("running {} simulations", runs.len());
+    let reports = sweep.execute_all(&runs);
+    match out {
+        Some(path) => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            write_table(&mut writer, &runs, &reports)?;
+            writer.flush()?;
+        }
+        None => write_table(&mut io::stdout().lock(), &runs, &reports)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mean_ci() {
+        assert_eq!((0.0, 0.0), mean_ci(&[]));
+        assert_eq!((3.0, 0.0), mean_ci(&[3.0]));
+        let (mean, ci) = mean_ci(&[1.0, 2.0, 3.0]);
+        assert_eq!(2.0, mean);
+        // sd 1, n 3, t 4.303
+        assert!((ci - 4.303 / 3f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sweep() {
+        let sweep = Sweep {
+            seeds: vec![1, 2],
+            grid_sizes: vec![(10, 10), (20, 15)],
+            agents: vec![2],
+            policies: vec!["closest".to_string(), "value".to_string()],
+            ticks: 20,
+            threads: Some(3),
+            ..Default::default()
+        };
+        let runs = sweep.runs().unwrap();
+        assert_eq!(8, runs.len());
+        let reports = sweep.execute_all(&runs);
+        assert_eq!(8, reports.len());
+        // same seed and setting, same result
+        assert_eq!(reports[0].total_score, sweep.execute(&runs[0]).total_score);
+        let mut out = Vec::new();
+        write_table(&mut out, &runs, &reports).unwrap();
+        let table = String::from_utf8(out).unwrap();
+        assert_eq!(5, table.lines().count());
+        assert!(table
+            .lines()
+            .nth(3)
+            .unwrap()
+            .starts_with("20,15,2,Closest,2,"));
+    }
+}
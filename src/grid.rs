@@ -1,80 +1,93 @@
 use log::debug;
 use rand::Rng;
 
-use crate::{location::Location, COLS, ROWS};
+use crate::location::{Direction, Location};
 
 pub(crate) struct Grid {
+    pub cols: u16,
+    pub rows: u16,
     pub occupancy: Vec<Option<()>>,
 }
 
 impl Grid {
-    pub(crate) fn new() -> Grid {
+    pub(crate) fn new(cols: u16, rows: u16) -> Grid {
         Grid {
-            occupancy: vec![None; COLS as usize * ROWS as usize],
+            cols,
+            rows,
+            occupancy: vec![None; cols as usize * rows as usize],
+        }
+    }
+
+    fn index(&self, location: Location) -> usize {
+        location.row as usize * self.cols as usize + location.col as usize
+    }
+
+    pub(crate) fn is_valid_move(&self, location: Location, d: Direction) -> bool {
+        match d {
+            Direction::Up => location.row > 0,
+            Direction::Down => location.row < self.rows - 1,
+            Direction::Left => location.col > 0,
+            Direction::Right => location.col < self.cols - 1,
         }
     }
 
     pub(crate) fn set(&mut self, location: Location) {
-        let index = (location.row as usize * COLS as usize + location.col as usize) as usize;
+        let index = self.index(location);
         self.occupancy[index] = Some(());
         debug!("set {:?}: count={}", location, self.count());
     }
 
     pub(crate) fn remove(&mut self, location: Location) {
-        let index = (location.row as usize * COLS as usize + location.col as usize) as usize;
+        let index = self.index(location);
         self.occupancy[index] = None;
         debug!("remove {:?}: count={}", location, self.count());
     }
 
     pub(crate) fn is_free(&self, location: Location) -> bool {
-        assert!((location.row as usize) < ROWS as usize);
-        assert!((location.col as usize) < COLS as usize);
-        let index = (location.row as usize * COLS as usize + location.col as usize) as usize;
+        assert!(location.row < self.rows);
+        assert!(location.col < self.cols);
+        let index = self.index(location);
         self.occupancy[index].is_none()
     }
 
-    pub fn random_location(&self) -> Location {
-        let mut rng = rand::thread_rng();
-        let mut c: u16 = rng.gen_range(0..COLS);
-        let mut r: u16 = rng.gen_range(0..ROWS);
+    pub fn random_location(&self, rng: &mut impl Rng) -> Location {
+        let mut c: u16 = rng.gen_range(0..self.cols);
+        let mut r: u16 = rng.gen_range(0..self.rows);
 
         let mut new_loc = Location::new(c, r);
         while !self.is_free(new_loc) {
-            c = rng.gen_range(0..COLS);
-            r = rng.gen_range(0..ROWS);
+            c = rng.gen_range(0..self.cols);
+            r = rng.gen_range(0..self.rows);
             new_loc = Location::new(c, r);
         }
         new_loc
     }
 
-    fn count(&self) -> usize {
-        self.occupancy.iter().filter(|o| o.is_some()).count()
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn occupied(&self) -> impl Iterator<Item = Location> + '_ {
+        self.occupancy
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| o.is_some())
+            .map(|(i, _)| {
+                let cols = self.cols as usize;
+                Location::new((i % cols) as u16, (i / cols) as u16)
+            })
     }
 
-    pub fn print_grid(&self) {
-        for r in 0..ROWS {
-            for c in 0..COLS {
-                let loc = Location::new(c, r);
-                if self.is_free(loc) {
-                    print!("0");
-                } else {
-                    print!("1");
-                }
-            }
-            println!();
-        }
-        println!("Objects: {}", self.count());
+    fn count(&self) -> usize {
+        self.occupancy.iter().filter(|o| o.is_some()).count()
     }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::{grid::Grid, location::Location};
+    use crate::{grid::Grid, location::Location, COLS, ROWS};
 
     #[test]
     fn test_grid() {
-        let mut grid = Grid::new();
+        let mut grid = Grid::new(COLS, ROWS);
         assert!(grid.is_free(Location::new(0, 0)));
         assert_eq!(0, grid.count());
 
@@ -93,9 +106,10 @@ mod tests {
 
     #[test]
     fn test_count() {
-        let mut grid = Grid::new();
+        let mut grid = Grid::new(COLS, ROWS);
+        let mut rng = rand::thread_rng();
         for i in 1..100 {
-            let loc = grid.random_location();
+            let loc = grid.random_location(&mut rng);
             assert!(grid.is_free(loc));
             grid.set(loc);
             assert_eq!(i, grid.count());
@@ -103,10 +117,11 @@ mod tests {
     }
 
     #[test]
-    fn test_print() {
-        let mut grid = Grid::new();
-        grid.set(Location::new(1, 0));
-        grid.set(Location::new(0, 9));
-        grid.print_grid();
+    fn test_occupied() {
+        let mut grid = Grid::new(COLS, ROWS);
+        grid.set(Location::new(3, 0));
+        grid.set(Location::new(1, 2));
+        let occupied: Vec<Location> = grid.occupied().collect();
+        assert_eq!(vec![Location::new(3, 0), Location::new(1, 2)], occupied);
     }
 }
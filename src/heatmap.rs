@@ -0,0 +1,184 @@
+use std::str::FromStr;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use crate::location::Location;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::raster::Image;
+
+/// Pixels per cell in exported images.
+#[cfg(not(target_arch = "wasm32"))]
+const SCALE: usize = 8;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HeatmapKind {
+    /// ticks agents spent on a cell
+    #[default]
+    Occupancy,
+    /// times agents walked into a cell
+    Traversal,
+}
+
+impl FromStr for HeatmapKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "occupancy" => Ok(HeatmapKind::Occupancy),
+            "traversal" => Ok(HeatmapKind::Traversal),
+            _ => Err(format!("unknown heatmap: {}", s)),
+        }
+    }
+}
+
+/// Where agents spend their time and where they walk, summed up over the run.
+#[derive(Debug, Clone)]
+pub(crate) struct Heatmap {
+    cols: u16,
+    #[cfg(not(target_arch = "wasm32"))]
+    rows: u16,
+    occupancy: Vec<u32>,
+    traversal: Vec<u32>,
+}
+
+impl Heatmap {
+    pub(crate) fn new(cols: u16, rows: u16) -> Self {
+        let cells = cols as usize * rows as usize;
+        Heatmap {
+            cols,
+            #[cfg(not(target_arch = "wasm32"))]
+            rows,
+            occupancy: vec![0; cells],
+            traversal: vec![0; cells],
+        }
+    }
+
+    fn index(&self, location: Location) -> usize {
+        location.row as usize * self.cols as usize + location.col as usize
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn counts(&self, kind: HeatmapKind) -> &[u32] {
+        match kind {
+            HeatmapKind::Occupancy => &self.occupancy,
+            HeatmapKind::Traversal => &self.traversal,
+        }
+    }
+
+    /// An agent spent a tick on `location`.
+    pub(crate) fn occupied(&mut self, location: Location) {
+        let index = self.index(location);
+        self.occupancy[index] += 1;
+    }
+
+    /// An agent walked into `location`.
+    pub(crate) fn traversed(&mut self, location: Location) {
+        let index = self.index(location);
+        self.traversal[index] += 1;
+    }
+
+    /// How hot every cell is from 0 to 1 relative to the hottest one, row by row.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn heats(&self, kind: HeatmapKind) -> impl Iterator<Item = (Location, f32)> + '_ {
+        let counts = self.counts(kind);
+        let max = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+        let cols = self.cols as usize;
+        counts.iter().enumerate().map(move |(i, &count)| {
+            let location = Location::new((i % cols) as u16, (i / cols) as u16);
+            (location, count as f32 / max)
+        })
+    }
+
+    /// Writes both heatmaps as CSV if `path` ends in `.csv`, otherwise `kind` as a PPM image.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn save(&self, path: &str, kind: HeatmapKind) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        if path.ends_with(".csv") {
+            self.write_csv(&mut out)?;
+        } else {
+            self.write_ppm(&mut out, kind)?;
+        }
+        out.flush()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "col,row,occupancy,traversal")?;
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let index = self.index(Location::new(col, row));
+                writeln!(
+                    out,
+                    "{},{},{},{}",
+                    col, row, self.occupancy[index], self.traversal[index]
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write_ppm(&self, out: &mut impl Write, kind: HeatmapKind) -> io::Result<()> {
+        let (cols, rows) = (self.cols as usize, self.rows as usize);
+        let mut image = Image::new(cols * SCALE, rows * SCALE);
+        for (location, heat) in self.heats(kind) {
+            let (x, y) = (location.col as usize * SCALE, location.row as usize * SCALE);
+            image.fill(x, y, SCALE, SCALE, hot(heat));
+        }
+        image.write_ppm(out)
+    }
+}
+
+/// Black through red and yellow to white as `heat` goes from 0 to 1.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn hot(heat: f32) -> (u8, u8, u8) {
+    let channel = |from: f32| ((heat * 3.0 - from).clamp(0.0, 1.0) * 255.0).round() as u8;
+    (channel(0.0), channel(1.0), channel(2.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heat() {
+        let mut heatmap = Heatmap::new(3, 2);
+        heatmap.occupied(Location::new(1, 1));
+        heatmap.occupied(Location::new(1, 1));
+        heatmap.occupied(Location::new(2, 0));
+        heatmap.traversed(Location::new(2, 0));
+        let heats: Vec<_> = heatmap.heats(HeatmapKind::Occupancy).collect();
+        assert_eq!((Location::new(1, 1), 1.0), heats[4]);
+        assert_eq!((Location::new(2, 0), 0.5), heats[2]);
+        let traversal: Vec<_> = heatmap.heats(HeatmapKind::Traversal).collect();
+        assert_eq!((Location::new(1, 1), 0.0), traversal[4]);
+        assert_eq!(6, traversal.len());
+
+        let mut csv = Vec::new();
+        heatmap.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(7, csv.lines().count());
+        assert!(csv.contains("\n2,0,1,1\n"));
+        assert!(csv.contains("\n1,1,2,0\n"));
+
+        let mut ppm = Vec::new();
+        heatmap.write_ppm(&mut ppm, HeatmapKind::Occupancy).unwrap();
+        let header = b"P6\n24 16\n255\n";
+        assert_eq!(header, &ppm[..header.len()]);
+        assert_eq!(header.len() + 24 * 16 * 3, ppm.len());
+        let pixel = |x: usize, y: usize| header.len() + (y * 24 + x) * 3;
+        // the first pixel of the hottest cell and the last pixel, in a cell nobody was on
+        assert_eq!(&[255, 255, 255], &ppm[pixel(8, 8)..pixel(8, 8) + 3]);
+        assert_eq!(&[0, 0, 0], &ppm[pixel(23, 15)..]);
+    }
+
+    #[test]
+    fn test_hot() {
+        assert_eq!((0, 0, 0), hot(0.0));
+        assert_eq!((255, 0, 0), hot(1.0 / 3.0));
+        assert_eq!((255, 255, 255), hot(1.0));
+    }
+}
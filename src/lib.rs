@@ -0,0 +1,59 @@
+// the browser build leaves out what only the command line uses
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use app::run;
+pub use env::{Env, Info, Observation, Step};
+
+mod agent;
+// the command line, the window and the terminal, the browser only gets the simulation
+#[cfg(not(target_arch = "wasm32"))]
+mod app;
+mod astar;
+mod components;
+mod config;
+#[cfg(not(target_arch = "wasm32"))]
+mod dashboard;
+mod decay;
+mod ecs;
+#[cfg(not(target_arch = "wasm32"))]
+mod editor;
+mod env;
+mod events;
+#[cfg(not(target_arch = "wasm32"))]
+mod experiment;
+mod grid;
+mod heatmap;
+mod location;
+mod message;
+mod metrics;
+#[cfg(not(target_arch = "wasm32"))]
+mod overlay;
+#[cfg(not(target_arch = "wasm32"))]
+mod player;
+#[cfg(feature = "python")]
+mod python;
+mod raster;
+#[cfg(not(target_arch = "wasm32"))]
+mod replay;
+mod schedule;
+#[cfg(not(target_arch = "wasm32"))]
+mod server;
+#[cfg(not(target_arch = "wasm32"))]
+mod snapshot;
+#[cfg(not(target_arch = "wasm32"))]
+mod state;
+mod systems;
+mod team;
+mod text;
+#[cfg(not(target_arch = "wasm32"))]
+mod tui;
+#[cfg(target_arch = "wasm32")]
+mod web;
+mod world;
+
+const COLS: u16 = 40;
+const ROWS: u16 = 40;
+const NUM_AGENTS: u16 = 6;
+const NUM_TILES: u8 = 20;
+const NUM_HOLES: u8 = 20;
+const NUM_OBSTACLES: u8 = 20;
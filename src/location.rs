@@ -1,8 +1,6 @@
-use std::{cell::RefCell, rc::Rc};
+use serde::{Deserialize, Serialize};
 
-use crate::{COLS, ROWS};
-
-#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
+#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Down,
@@ -10,12 +8,7 @@ pub enum Direction {
     Right,
 }
 
-pub(crate) trait Located {
-    fn location(&self) -> Location;
-    fn set_location(&mut self, location: Location);
-}
-
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub struct Location {
     pub col: u16,
     pub row: u16,
@@ -25,74 +18,60 @@ impl Location {
     pub fn new(c: u16, r: u16) -> Location {
         Location { col: c, row: r }
     }
+    /// The neighbouring location, the grid decides whether it can be moved to, see `Grid::is_valid_move`.
     pub fn next_location(&self, d: Direction) -> Location {
         match d {
-            Direction::Up => {
-                if self.row > 0 {
-                    Location::new(self.col, self.row - 1)
-                } else {
-                    *self
-                }
-            }
-            Direction::Down => {
-                if self.row < ROWS - 1 {
-                    Location::new(self.col, self.row + 1)
-                } else {
-                    *self
-                }
-            }
-            Direction::Left => {
-                if self.col > 0 {
-                    Location::new(self.col - 1, self.row)
-                } else {
-                    *self
-                }
-            }
-            Direction::Right => {
-                if self.col < COLS - 1 {
-                    Location::new(self.col + 1, self.row)
-                } else {
-                    *self
-                }
-            }
-        }
-    }
-    pub fn is_valid_move(&self, d: Direction) -> bool {
-        match d {
-            Direction::Up => self.row > 0,
-            Direction::Down => self.row < ROWS - 1,
-            Direction::Left => self.col > 0,
-            Direction::Right => self.col < COLS - 1,
+            Direction::Up => Location::new(self.col, self.row.saturating_sub(1)),
+            Direction::Down => Location::new(self.col, self.row + 1),
+            Direction::Left => Location::new(self.col.saturating_sub(1), self.row),
+            Direction::Right => Location::new(self.col + 1, self.row),
         }
     }
     pub fn distance(&self, other: Location) -> u16 {
-        let col_diff = if self.col > other.col {
-            self.col - other.col
-        } else {
-            other.col - self.col
-        };
-        let row_diff = if self.row > other.row {
-            self.row - other.row
-        } else {
-            other.row - self.row
-        };
+        let col_diff = self.col.abs_diff(other.col);
+        let row_diff = self.row.abs_diff(other.row);
         col_diff + row_diff
     }
 }
 
-pub(crate) fn closest<T: Located>(
+/// The candidate nearest to `our`.
+pub(crate) fn closest<T>(
     our: Location,
-    list: &Vec<Rc<RefCell<T>>>,
-) -> Option<Rc<RefCell<T>>> {
+    candidates: impl IntoIterator<Item = (T, Location)>,
+) -> Option<(T, Location)> {
     let mut closest = None;
     let mut closest_distance = u16::MAX;
-    for o in list.into_iter() {
-        let loc = o.borrow().location();
+    for (o, loc) in candidates {
         let dist = our.distance(loc);
         if dist < closest_distance {
             closest_distance = dist;
-            closest = Some(o.clone());
+            closest = Some((o, loc));
         }
     }
     closest
 }
+
+/// The candidate worth the most per step, `value` gets the distance to it
+/// and returns what the candidate will be worth once we get there.
+pub(crate) fn best<T: Copy>(
+    our: Location,
+    candidates: impl IntoIterator<Item = (T, Location)>,
+    value: impl Fn(T, u16) -> u8,
+) -> Option<(T, Location)> {
+    let mut best = None;
+    let mut best_utility = 0;
+    let mut best_distance = u16::MAX;
+    for (o, loc) in candidates {
+        let dist = our.distance(loc);
+        let utility = value(o, dist) as u32 * 1000 / (dist as u32 + 1);
+        if best.is_none()
+            || utility > best_utility
+            || (utility == best_utility && dist < best_distance)
+        {
+            best_utility = utility;
+            best_distance = dist;
+            best = Some((o, loc));
+        }
+    }
+    best
+}
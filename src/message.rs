@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{agent::AgentId, location::Location};
+
+/// What agents tell each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Content {
+    /// the sender is going for the tile or hole there
+    Claim(Location),
+    /// there is a tile there
+    Tile(Location),
+    /// there is a hole there
+    Hole(Location),
+}
+
+impl Content {
+    pub(crate) fn location(&self) -> Location {
+        match *self {
+            Content::Claim(l) | Content::Tile(l) | Content::Hole(l) => l,
+        }
+    }
+}
+
+/// A message an agent wants to send, to one agent or to everyone in range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Outgoing {
+    #[serde(default)]
+    pub to: Option<AgentId>,
+    pub content: Content,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Message {
+    pub from: AgentId,
+    /// `None` for a broadcast
+    pub to: Option<AgentId>,
+    pub sent: u64,
+    pub content: Content,
+}
+
+/// How far, how many and how fast messages go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Comms {
+    /// agents built in tell each other what they go for and, with limited sight, what they see
+    pub enabled: bool,
+    /// how many steps away a message reaches, everyone when unset
+    pub range: Option<u16>,
+    /// how many messages an agent sends a tick, the rest is dropped, no limit when unset
+    pub bandwidth: Option<u16>,
+    /// ticks until a message is read, at least 1
+    pub delay: u64,
+}
+
+impl Default for Comms {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            range: None,
+            bandwidth: None,
+            delay: 1,
+        }
+    }
+}
+
+/// Messages on their way and the ones that arrived this tick.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct Channel {
+    /// who gets each message and the tick it arrives
+    in_flight: Vec<(u64, AgentId, Message)>,
+    inboxes: BTreeMap<AgentId, Vec<Message>>,
+}
+
+impl Channel {
+    pub(crate) fn post(&mut self, arrives: u64, to: AgentId, message: Message) {
+        self.in_flight.push((arrives, to, message));
+    }
+
+    /// Fills the inboxes with what arrives at `tick`, what was there before is gone.
+    pub(crate) fn deliver(&mut self, tick: u64) -> impl Iterator<Item = (AgentId, usize)> + '_ {
+        self.inboxes.clear();
+        let inboxes = &mut self.inboxes;
+        self.in_flight.retain(|&(arrives, to, message)| {
+            if arrives > tick {
+                return true;
+            }
+            inboxes.entry(to).or_default().push(message);
+            false
+        });
+        inboxes
+            .iter()
+            .map(|(&agent, messages)| (agent, messages.len()))
+    }
+
+    pub(crate) fn inbox(&self, agent: AgentId) -> &[Message] {
+        self.inboxes.get(&agent).map_or(&[], |m| m.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deliver() {
+        let mut channel = Channel::default();
+        let message = |sent| Message {
+            from: 0,
+            to: None,
+            sent,
+            content: Content::Tile(Location::new(1, 2)),
+        };
+        channel.post(2, 1, message(1));
+        channel.post(3, 1, message(2));
+        channel.post(3, 2, message(2));
+        assert!(channel.deliver(1).next().is_none());
+        assert_eq!(vec![(1, 1)], channel.deliver(2).collect::<Vec<_>>());
+        assert_eq!(&[message(1)], channel.inbox(1));
+        let mut arrived: Vec<_> = channel.deliver(3).collect();
+        arrived.sort();
+        assert_eq!(vec![(1, 1), (2, 1)], arrived);
+        assert_eq!(&[message(2)], channel.inbox(1));
+        assert!(channel.deliver(4).next().is_none());
+        assert!(channel.inbox(1).is_empty());
+    }
+}
@@ -0,0 +1,232 @@
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::agent::AgentId;
+
+/// Counters an agent keeps about itself while it runs.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct AgentMetrics {
+    pub distance: u64,
+    pub idle_ticks: u64,
+    pub failed_plans: u64,
+    pub tiles_delivered: u64,
+    /// tiles taken from agents of other teams
+    #[serde(default)]
+    pub tiles_stolen: u64,
+    /// messages within the bandwidth, a broadcast counts once
+    #[serde(default)]
+    pub messages_sent: u64,
+    #[serde(default)]
+    pub messages_received: u64,
+    /// messages over the bandwidth
+    #[serde(default)]
+    pub messages_dropped: u64,
+    /// ticks spent carrying the delivered tiles
+    pub delivery_ticks: u64,
+    /// tick the current tile was picked up
+    pub carrying_since: Option<u64>,
+    pub nodes_expanded: u64,
+    pub planning_nanos: u64,
+}
+
+impl AgentMetrics {
+    pub(crate) fn planned(&mut self, nodes: usize, time: Duration) {
+        self.nodes_expanded += nodes as u64;
+        self.planning_nanos += time.as_nanos() as u64;
+    }
+
+    pub(crate) fn picked_up(&mut self, tick: u64) {
+        self.carrying_since = Some(tick);
+    }
+
+    pub(crate) fn delivered(&mut self, tick: u64) {
+        self.tiles_delivered += 1;
+        if let Some(since) = self.carrying_since.take() {
+            self.delivery_ticks += tick - since;
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn average_delivery_ticks(&self) -> f64 {
+        if self.tiles_delivered == 0 {
+            0.0
+        } else {
+            self.delivery_ticks as f64 / self.tiles_delivered as f64
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct AgentReport {
+    pub agent: AgentId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team: Option<AgentId>,
+    pub score: u32,
+    pub distance: u64,
+    pub idle_ticks: u64,
+    pub failed_plans: u64,
+    pub tiles_delivered: u64,
+    pub tiles_stolen: u64,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    pub messages_dropped: u64,
+    pub average_delivery_ticks: f64,
+    pub nodes_expanded: u64,
+    pub planning_ms: f64,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AgentReport {
+    pub(crate) fn new(
+        agent: AgentId,
+        team: Option<AgentId>,
+        score: u32,
+        metrics: &AgentMetrics,
+    ) -> AgentReport {
+        AgentReport {
+            agent,
+            team,
+            score,
+            distance: metrics.distance,
+            idle_ticks: metrics.idle_ticks,
+            failed_plans: metrics.failed_plans,
+            tiles_delivered: metrics.tiles_delivered,
+            tiles_stolen: metrics.tiles_stolen,
+            messages_sent: metrics.messages_sent,
+            messages_received: metrics.messages_received,
+            messages_dropped: metrics.messages_dropped,
+            average_delivery_ticks: metrics.average_delivery_ticks(),
+            nodes_expanded: metrics.nodes_expanded,
+            planning_ms: metrics.planning_nanos as f64 / 1_000_000.0,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct TeamReport {
+    pub team: AgentId,
+    pub score: u32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct RunReport {
+    pub ticks: u64,
+    pub total_score: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub teams: Vec<TeamReport>,
+    pub agents: Vec<AgentReport>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RunReport {
+    pub(crate) fn new(ticks: u64, agents: Vec<AgentReport>) -> RunReport {
+        let mut teams: BTreeMap<AgentId, u32> = BTreeMap::new();
+        for a in agents.iter() {
+            if let Some(team) = a.team {
+                *teams.entry(team).or_default() += a.score;
+            }
+        }
+        RunReport {
+            ticks,
+            total_score: agents.iter().map(|a| a.score).sum(),
+            teams: teams
+                .into_iter()
+                .map(|(team, score)| TeamReport { team, score })
+                .collect(),
+            agents,
+        }
+    }
+
+    /// Writes CSV for `.csv` files and JSON otherwise.
+    pub(crate) fn save(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        if path.ends_with(".csv") {
+            self.write_csv(&mut writer)?;
+        } else {
+            serde_json::to_writer_pretty(&mut writer, self)?;
+        }
+        writer.flush()
+    }
+
+    fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "ticks,agent,team,score,distance,idle_ticks,failed_plans,tiles_delivered,tiles_stolen,messages_sent,messages_received,messages_dropped,average_delivery_ticks,nodes_expanded,planning_ms"
+        )?;
+        for a in self.agents.iter() {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{:.2},{},{:.3}",
+                self.ticks,
+                a.agent,
+                a.team.map_or(String::new(), |t| t.to_string()),
+                a.score,
+                a.distance,
+                a.idle_ticks,
+                a.failed_plans,
+                a.tiles_delivered,
+                a.tiles_stolen,
+                a.messages_sent,
+                a.messages_received,
+                a.messages_dropped,
+                a.average_delivery_ticks,
+                a.nodes_expanded,
+                a.planning_ms
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delivery_time() {
+        let mut metrics = AgentMetrics::default();
+        assert_eq!(0.0, metrics.average_delivery_ticks());
+        metrics.picked_up(10);
+        metrics.delivered(14);
+        metrics.picked_up(20);
+        metrics.delivered(30);
+        assert_eq!(2, metrics.tiles_delivered);
+        assert_eq!(7.0, metrics.average_delivery_ticks());
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut metrics = AgentMetrics {
+            distance: 12,
+            ..Default::default()
+        };
+        metrics.planned(40, Duration::from_micros(1500));
+        let report = RunReport::new(
+            100,
+            vec![
+                AgentReport::new(0, Some(1), 7, &metrics),
+                AgentReport::new(1, Some(1), 3, &AgentMetrics::default()),
+            ],
+        );
+        assert_eq!(10, report.total_score);
+        assert_eq!(vec![TeamReport { team: 1, score: 10 }], report.teams);
+        let mut out = Vec::new();
+        report.write_csv(&mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        assert_eq!(
+            "100,0,1,7,12,0,0,0,0,0,0,0,0.00,40,1.500",
+            csv.lines().nth(1).unwrap()
+        );
+    }
+}
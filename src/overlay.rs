@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use bracket_lib::prelude::*;
+
+use crate::{
+    heatmap::{hot, HeatmapKind},
+    location::Location,
+    world::World,
+};
+
+/// Planning details drawn under the entities, toggled with F1 to F4.
+#[derive(Debug, Default)]
+pub(crate) struct Overlays {
+    /// the rest of each agent's A* path
+    paths: bool,
+    /// a line from each agent to its target
+    targets: bool,
+    /// the cells the last search of each agent expanded
+    expanded: bool,
+    /// targets agents are going for, in yellow when more than one goes for it
+    claims: bool,
+    /// cells colored by how much agents use them
+    heatmap: Option<HeatmapKind>,
+}
+
+impl Overlays {
+    /// Returns whether `key` toggled an overlay.
+    pub(crate) fn handle_key(&mut self, key: VirtualKeyCode) -> bool {
+        let overlay = match key {
+            VirtualKeyCode::F1 => &mut self.paths,
+            VirtualKeyCode::F2 => &mut self.targets,
+            VirtualKeyCode::F3 => &mut self.expanded,
+            VirtualKeyCode::F4 => &mut self.claims,
+            VirtualKeyCode::F5 => {
+                self.heatmap = match self.heatmap {
+                    None => Some(HeatmapKind::Occupancy),
+                    Some(HeatmapKind::Occupancy) => Some(HeatmapKind::Traversal),
+                    Some(HeatmapKind::Traversal) => None,
+                };
+                return true;
+            }
+            _ => return false,
+        };
+        *overlay = !*overlay;
+        true
+    }
+
+    /// Draws paths, target lines and expanded cells, entities get drawn over them.
+    pub(crate) fn render_under(&self, world: &World, ctx: &mut BTerm) {
+        if let Some(kind) = self.heatmap {
+            for (location, heat) in world.heatmap.heats(kind) {
+                // the hot end is bright, so it stays readable under black glyphs
+                let bg = RGB::named(hot(heat)).lerp(RGB::named(WHITE), 0.3);
+                ctx.set(location.col, location.row, BLACK, bg, 32);
+            }
+        }
+        for (&entity, plan) in world.plans.iter() {
+            let (Some(agent), Some(&location)) = (
+                world.entities.agents.get(entity),
+                world.entities.positions.get(entity),
+            ) else {
+                continue;
+            };
+            let color = RGB::named(agent.color());
+            if let (true, Some(target)) = (self.targets, plan.target) {
+                let line = line2d_bresenham(point(location), point(target));
+                for p in line.iter().skip(1) {
+                    ctx.set(p.x, p.y, color, RGB::named(WHITE), 250);
+                }
+            }
+            if self.paths {
+                let mut cell = location;
+                for &direction in plan.path.iter() {
+                    cell = cell.next_location(direction);
+                    ctx.set(cell.col, cell.row, color, RGB::named(WHITE), 249);
+                }
+            }
+            if self.expanded {
+                for cell in plan.expanded.iter() {
+                    ctx.set_bg(cell.col, cell.row, light(color));
+                }
+            }
+        }
+    }
+
+    /// Marks the claimed targets, on top of the tiles and holes themselves.
+    pub(crate) fn render_over(&self, world: &World, ctx: &mut BTerm) {
+        if !self.claims {
+            return;
+        }
+        let mut claimed: HashMap<Location, Vec<RGB>> = HashMap::new();
+        for (&entity, plan) in world.plans.iter() {
+            if let (Some(agent), Some(target)) = (world.entities.agents.get(entity), plan.target) {
+                let color = light(RGB::named(agent.color()));
+                claimed.entry(target).or_default().push(color);
+            }
+        }
+        for (target, claimers) in claimed {
+            let bg = match claimers.as_slice() {
+                [one] => *one,
+                _ => RGB::named(YELLOW),
+            };
+            ctx.set_bg(target.col, target.row, bg);
+        }
+    }
+
+    /// Lists the overlays and whether they're on in the sidebar.
+    pub(crate) fn render_legend(&self, world: &World, ctx: &mut BTerm) {
+        let (cols, rows) = (world.config.cols, world.config.rows);
+        let overlays = [
+            ("F1 paths", self.paths),
+            ("F2 targets", self.targets),
+            ("F3 expanded", self.expanded),
+            ("F4 claims", self.claims),
+            ("F5 heatmap", self.heatmap.is_some()),
+        ];
+        for (i, (name, on)) in overlays.iter().enumerate() {
+            let row = rows.saturating_sub(11 - i as u16);
+            let color = if *on { BLACK } else { GRAY };
+            ctx.print_color(cols + 3, row, color, WHITE, *name);
+        }
+    }
+}
+
+fn light(color: RGB) -> RGB {
+    color.lerp(RGB::named(WHITE), 0.75)
+}
+
+fn point(location: Location) -> Point {
+    Point::new(location.col, location.row)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle() {
+        let mut overlays = Overlays::default();
+        assert!(overlays.handle_key(VirtualKeyCode::F3));
+        assert!(overlays.expanded);
+        assert!(!overlays.paths);
+        overlays.handle_key(VirtualKeyCode::F3);
+        assert!(!overlays.expanded);
+        overlays.handle_key(VirtualKeyCode::F5);
+        assert_eq!(Some(HeatmapKind::Occupancy), overlays.heatmap);
+        overlays.handle_key(VirtualKeyCode::F5);
+        overlays.handle_key(VirtualKeyCode::F5);
+        assert_eq!(None, overlays.heatmap);
+        assert!(!overlays.handle_key(VirtualKeyCode::F6));
+    }
+}
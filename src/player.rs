@@ -0,0 +1,103 @@
+use bracket_lib::prelude::VirtualKeyCode;
+
+use crate::{
+    agent::{Action, AgentId},
+    ecs::Entity,
+    location::Direction,
+    world::World,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Input {
+    Move(Direction),
+    /// pick up or drop into the cell the agent faces
+    Grab,
+}
+
+/// An agent played from the keyboard, it walks with the arrow keys or WASD and picks up and drops with E.
+pub(crate) struct Player {
+    pub agent: AgentId,
+    facing: Direction,
+    next: Option<Input>,
+}
+
+impl Player {
+    pub(crate) fn new(agent: AgentId) -> Self {
+        Player {
+            agent,
+            facing: Direction::Right,
+            next: None,
+        }
+    }
+
+    /// Returns whether `key` was for the player, the last key before a tick wins.
+    pub(crate) fn handle_key(&mut self, key: VirtualKeyCode) -> bool {
+        let input = match key {
+            VirtualKeyCode::Up | VirtualKeyCode::W => Input::Move(Direction::Up),
+            VirtualKeyCode::Down | VirtualKeyCode::S => Input::Move(Direction::Down),
+            VirtualKeyCode::Left | VirtualKeyCode::A => Input::Move(Direction::Left),
+            VirtualKeyCode::Right | VirtualKeyCode::D => Input::Move(Direction::Right),
+            VirtualKeyCode::E => Input::Grab,
+            _ => return false,
+        };
+        if let Input::Move(direction) = input {
+            self.facing = direction;
+        }
+        self.next = Some(input);
+        true
+    }
+
+    /// What the player's agent does this tick, walking only goes to empty cells,
+    /// tiles and holes need a grab.
+    pub(crate) fn action(&mut self, world: &World) -> Option<(Entity, Action)> {
+        let entity = world.entities.agent(self.agent)?;
+        let location = *world.entities.positions.get(entity)?;
+        let grabbable = |direction: Direction| {
+            world.grid.is_valid_move(location, direction)
+                && world
+                    .entities
+                    .at(location.next_location(direction))
+                    .is_some_and(|e| {
+                        world.entities.pickups.contains(e) || world.entities.receptacles.contains(e)
+                    })
+        };
+        let action = match self.next.take() {
+            Some(Input::Move(direction)) if !grabbable(direction) => Action::Move(direction),
+            Some(Input::Grab) if grabbable(self.facing) => Action::Move(self.facing),
+            _ => Action::Wait,
+        };
+        Some((entity, action))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        events::EntityKind,
+        location::Location,
+        world::tests::{empty_world, put},
+    };
+
+    #[test]
+    fn test_grab() {
+        let mut world = empty_world();
+        put(&mut world, EntityKind::Tile, Location::new(1, 0), 3);
+
+        let mut player = Player::new(0);
+        assert_eq!(Some((0, Action::Wait)), player.action(&world));
+        // walking into the tile doesn't pick it up
+        player.handle_key(VirtualKeyCode::D);
+        assert_eq!(Some((0, Action::Wait)), player.action(&world));
+        player.handle_key(VirtualKeyCode::E);
+        let right = Action::Move(Direction::Right);
+        assert_eq!(Some((0, right)), player.action(&world));
+        player.handle_key(VirtualKeyCode::S);
+        let down = Action::Move(Direction::Down);
+        assert_eq!(Some((0, down)), player.action(&world));
+        // now facing down, where there's nothing to grab
+        player.handle_key(VirtualKeyCode::E);
+        assert_eq!(Some((0, Action::Wait)), player.action(&world));
+        assert!(!player.handle_key(VirtualKeyCode::Q));
+    }
+}
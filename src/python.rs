@@ -0,0 +1,93 @@
+use numpy::{PyArray1, PyArray3, PyArrayMethods};
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    prelude::*,
+    types::PyDict,
+};
+
+use crate::env::{Env, Observation};
+
+/// `tileworld_engine.Env(["--agents", "2"])`, takes the same options as the command line.
+/// The event sink isn't `Sync`, so an environment stays on the thread that made it.
+#[pyclass(name = "Env", unsendable)]
+struct PyEnv(Env);
+
+type Array<'py> = Bound<'py, PyArray3<f32>>;
+type Transition<'py> = (
+    Array<'py>,
+    Bound<'py, PyArray1<f32>>,
+    bool,
+    Bound<'py, PyDict>,
+);
+
+#[pymethods]
+impl PyEnv {
+    #[new]
+    #[pyo3(signature = (args = Vec::new()))]
+    fn new(args: Vec<String>) -> PyResult<Self> {
+        Env::from_args(args.into_iter())
+            .map(PyEnv)
+            .map_err(PyValueError::new_err)
+    }
+
+    #[getter]
+    fn agents(&self) -> usize {
+        self.0.agents()
+    }
+
+    #[getter]
+    fn actions(&self) -> usize {
+        self.0.actions()
+    }
+
+    #[pyo3(signature = (seed = None))]
+    fn reset<'py>(&mut self, py: Python<'py>, seed: Option<u64>) -> PyResult<Array<'py>> {
+        array(py, self.0.reset(seed))
+    }
+
+    /// Returns `(observation, rewards, done, info)`, raises `RuntimeError` once the episode is done.
+    fn step<'py>(&mut self, py: Python<'py>, actions: Vec<usize>) -> PyResult<Transition<'py>> {
+        let step = self.0.step(&actions).map_err(PyRuntimeError::new_err)?;
+        let info = PyDict::new(py);
+        info.set_item("tick", step.info.tick)?;
+        info.set_item("scores", step.info.scores)?;
+        info.set_item("truncated", step.info.truncated)?;
+        info.set_item("messages", step.observation.messages.clone())?;
+        Ok((
+            array(py, step.observation)?,
+            PyArray1::from_vec(py, step.rewards),
+            step.done,
+            info,
+        ))
+    }
+
+    fn observe<'py>(&self, py: Python<'py>) -> PyResult<Array<'py>> {
+        array(py, self.0.observe())
+    }
+
+    /// What each agent reads next tick, as JSON strings.
+    fn messages(&self) -> Vec<Vec<String>> {
+        self.0.observe().messages
+    }
+
+    /// Sends a JSON message like `{"to": 1, "content": {"Claim": {"col": 2, "row": 7}}}` from `agent` with the
+    /// next step.
+    fn send(&mut self, agent: usize, message: &str) -> PyResult<()> {
+        self.0.send(agent, message).map_err(PyValueError::new_err)
+    }
+
+    /// Events of the last step as JSON strings.
+    fn events(&self) -> Vec<String> {
+        self.0.events()
+    }
+}
+
+fn array(py: Python<'_>, observation: Observation) -> PyResult<Array<'_>> {
+    let shape = [observation.channels, observation.rows, observation.cols];
+    PyArray1::from_vec(py, observation.data).reshape(shape)
+}
+
+#[pymodule]
+fn tileworld_engine(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyEnv>()
+}
@@ -0,0 +1,238 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+};
+
+use bracket_color::prelude::RGB;
+#[cfg(not(target_arch = "wasm32"))]
+use gif::{Encoder, Frame, Repeat};
+
+use crate::{
+    systems::{hole_color, tile_color},
+    world::World,
+};
+
+/// Pixels per cell.
+const CELL: usize = 8;
+/// Digits are drawn at this many pixels per font pixel.
+const DIGIT_SCALE: usize = 2;
+/// Height of a line in the score strip under the grid.
+const LINE: usize = 7 * DIGIT_SCALE;
+/// Width of an entry in the score strip, room for a color swatch and 5 digits.
+const ENTRY: usize = 12 + 5 * 4 * DIGIT_SCALE;
+/// Hundredths of a second per GIF frame.
+#[cfg(not(target_arch = "wasm32"))]
+const FRAME_DELAY: u16 = 10;
+
+/// 3x5 pixel digits, one bit per pixel, row by row from the top left.
+const DIGITS: [u16; 10] = [
+    0b111_101_101_101_111,
+    0b010_110_010_010_111,
+    0b111_001_111_100_111,
+    0b111_001_111_001_111,
+    0b101_101_111_001_001,
+    0b111_100_111_001_111,
+    0b111_100_111_101_111,
+    0b111_001_001_001_001,
+    0b111_101_111_101_111,
+    0b111_101_111_001_111,
+];
+
+pub(crate) type Color = (u8, u8, u8);
+
+const WHITE: Color = (255, 255, 255);
+const BLACK: Color = (0, 0, 0);
+const GRAY: Color = (96, 96, 96);
+
+/// An RGB image.
+pub(crate) struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub(crate) fn new(width: usize, height: usize) -> Self {
+        Image {
+            width,
+            height,
+            pixels: vec![255; width * height * 3],
+        }
+    }
+
+    pub(crate) fn fill(&mut self, x: usize, y: usize, w: usize, h: usize, (r, g, b): Color) {
+        for y in y..(y + h).min(self.height) {
+            for x in x..(x + w).min(self.width) {
+                let i = (y * self.width + x) * 3;
+                self.pixels[i..i + 3].copy_from_slice(&[r, g, b]);
+            }
+        }
+    }
+
+    /// Writes `number` with its top left corner at `(x, y)`.
+    fn number(&mut self, x: usize, y: usize, number: u64, color: Color) {
+        for (n, digit) in number.to_string().bytes().enumerate() {
+            let bits = DIGITS[(digit - b'0') as usize];
+            for pixel in 0..15 {
+                if bits & (1 << (14 - pixel)) != 0 {
+                    let px = x + (n * 4 + pixel % 3) * DIGIT_SCALE;
+                    let py = y + (pixel / 3) * DIGIT_SCALE;
+                    self.fill(px, py, DIGIT_SCALE, DIGIT_SCALE, color);
+                }
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn write_ppm(&self, out: &mut impl Write) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        out.write_all(&self.pixels)
+    }
+}
+
+pub(crate) fn bytes(color: RGB) -> Color {
+    let byte = |c: f32| (c * 255.0).round() as u8;
+    (byte(color.r), byte(color.g), byte(color.b))
+}
+
+/// Draws the grid with a strip under it with the tick and the score of every agent.
+pub(crate) fn render(world: &World) -> Image {
+    let (cols, rows) = (world.config.cols as usize, world.config.rows as usize);
+    let width = cols * CELL;
+    let per_line = (width / ENTRY).max(1);
+    // the tick takes the first entry
+    let lines = (world.entities.agents.len() + 1).div_ceil(per_line);
+    let mut image = Image::new(width, rows * CELL + lines * LINE + 2);
+    let entities = &world.entities;
+    for (entity, &location) in entities.positions.iter() {
+        let (x, y) = (location.col as usize * CELL, location.row as usize * CELL);
+        let value = entities.values.get(entity);
+        if let Some(agent) = entities.agents.get(entity) {
+            let carrying = entities
+                .carriers
+                .get(entity)
+                .is_some_and(|c| c.carrying.is_some());
+            image.fill(x, y, CELL, CELL, agent.color());
+            if !carrying {
+                image.fill(x + 2, y + 2, CELL - 4, CELL - 4, WHITE);
+            }
+        } else if let (Some(value), true) = (value, entities.pickups.contains(entity)) {
+            let color = bytes(tile_color(value.at(world.tick)));
+            image.fill(x + 1, y + 1, CELL - 2, CELL - 2, color);
+        } else if let (Some(value), true) = (value, entities.receptacles.contains(entity)) {
+            image.fill(x, y, CELL, CELL, bytes(hole_color(value, world.tick)));
+        } else {
+            image.fill(x, y, CELL, CELL, GRAY);
+        }
+    }
+    let top = rows * CELL;
+    image.fill(0, top, width, 1, BLACK);
+    let position = |i: usize| ((i % per_line) * ENTRY + 2, top + 3 + (i / per_line) * LINE);
+    let (x, y) = position(0);
+    image.number(x, y, world.tick, BLACK);
+    for (i, (_, agent)) in entities.agents.iter().enumerate() {
+        let (x, y) = position(i + 1);
+        image.fill(x, y, 8, 10, agent.color());
+        image.number(x + 12, y, agent.score as u64, BLACK);
+    }
+    image
+}
+
+/// Writes frames of a headless run as numbered PPM images and/or an animated GIF.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct Recorder {
+    frames: Option<PathBuf>,
+    gif: Option<String>,
+    /// opened with the first frame, when the size is known
+    encoder: Option<Encoder<BufWriter<File>>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Recorder {
+    pub(crate) fn new(frames: Option<&str>, gif: Option<&str>) -> io::Result<Recorder> {
+        if let Some(dir) = frames {
+            fs::create_dir_all(dir)?;
+        }
+        Ok(Recorder {
+            frames: frames.map(PathBuf::from),
+            gif: gif.map(str::to_string),
+            encoder: None,
+        })
+    }
+
+    pub(crate) fn capture(&mut self, world: &World) -> io::Result<()> {
+        let image = render(world);
+        if let Some(dir) = &self.frames {
+            let path = dir.join(format!("tick_{:05}.ppm", world.tick));
+            let mut out = BufWriter::new(File::create(path)?);
+            image.write_ppm(&mut out)?;
+            out.flush()?;
+        }
+        let (width, height) = (image.width as u16, image.height as u16);
+        if let (Some(path), None) = (&self.gif, &self.encoder) {
+            let out = BufWriter::new(File::create(path)?);
+            let mut encoder = Encoder::new(out, width, height, &[]).map_err(io::Error::other)?;
+            encoder
+                .set_repeat(Repeat::Infinite)
+                .map_err(io::Error::other)?;
+            self.encoder = Some(encoder);
+        }
+        if let Some(encoder) = self.encoder.as_mut() {
+            let mut frame = Frame::from_rgb_speed(width, height, &image.pixels, 10);
+            frame.delay = FRAME_DELAY;
+            encoder.write_frame(&frame).map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+
+    /// Ends the GIF.
+    pub(crate) fn finish(self) -> io::Result<()> {
+        if let Some(encoder) = self.encoder {
+            encoder.into_inner().map_err(io::Error::other)?.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, events::EventLog};
+
+    #[test]
+    fn test_number() {
+        let mut image = Image::new(20, 12);
+        image.number(0, 0, 17, BLACK);
+        let black = |x: usize, y: usize| image.pixels[(y * 20 + x) * 3] == 0;
+        // the 1 has its top pixel in the middle, the 7 its top row all set
+        assert!(!black(0, 0) && black(2, 0));
+        assert!(black(8, 0) && black(10, 0) && black(12, 0));
+        assert!(!black(8, 8) && black(12, 8));
+    }
+
+    #[test]
+    fn test_render() {
+        let config = Config {
+            cols: 20,
+            rows: 10,
+            agents: 3,
+            tiles: 5,
+            holes: 5,
+            obstacles: 5,
+            seed: Some(2),
+            ..Default::default()
+        };
+        let world = World::new(config, EventLog::default());
+        let image = render(&world);
+        assert_eq!(160, image.width);
+        // the tick and 3 agents fit in two lines
+        assert_eq!(80 + 2 * LINE + 2, image.height);
+        let location = world.entities.positions.get(0).unwrap();
+        let i = ((location.row as usize * CELL) * image.width + location.col as usize * CELL) * 3;
+        let agent = world.entities.agents.get(0).unwrap();
+        let (r, g, b) = agent.color();
+        assert_eq!([r, g, b], image.pixels[i..i + 3]);
+    }
+}
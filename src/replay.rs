@@ -0,0 +1,448 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+};
+
+use bracket_lib::prelude::*;
+
+use crate::{
+    agent::{Agent, AgentId, AgentState, Policy},
+    dashboard,
+    decay::Decay,
+    ecs::Entities,
+    events::{EntityKind, Event, Record},
+    state::SPEEDS,
+    systems, COLS, ROWS,
+};
+
+/// The world as far as it can be rebuilt from the event log.
+struct World {
+    cols: u16,
+    rows: u16,
+    decay: Decay,
+    entities: Entities,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World {
+            cols: COLS,
+            rows: ROWS,
+            decay: Decay::default(),
+            entities: Entities::default(),
+        }
+    }
+}
+
+impl World {
+    fn apply(&mut self, tick: u64, event: &Event) {
+        let entities = &mut self.entities;
+        match *event {
+            // a reset starts over with a new world
+            Event::WorldCreated { cols, rows, decay } => {
+                *self = World {
+                    cols,
+                    rows,
+                    decay,
+                    entities: Entities::default(),
+                }
+            }
+            Event::EntitySpawned {
+                kind,
+                id,
+                location,
+                score,
+            } => {
+                let score = score.unwrap_or_default();
+                if entities.kinds.contains(id) {
+                    entities.positions.insert(id, location);
+                    if let Some(value) = entities.values.get_mut(id) {
+                        *value = value.renewed(score, tick);
+                    }
+                } else {
+                    entities.spawn(id, kind, location, score, tick, &self.decay);
+                    if kind == EntityKind::Agent {
+                        let agent = Agent::new(entities.agents.len() as AgentId, Policy::default());
+                        entities.add_agent(id, agent);
+                    }
+                }
+            }
+            Event::AgentMoved { agent, to, .. } => {
+                if let Some(entity) = entities.agent(agent) {
+                    entities.positions.insert(entity, to);
+                }
+            }
+            Event::TilePickedUp { agent, tile, .. } => {
+                if let Some(entity) = entities.agent(agent) {
+                    let value = entities.values.get(tile).copied();
+                    if let Some(carrier) = entities.carriers.get_mut(entity) {
+                        carrier.carrying = value;
+                    }
+                    if let Some(agent) = entities.agents.get_mut(entity) {
+                        agent.state = AgentState::MoveToHole;
+                    }
+                }
+            }
+            Event::TileDropped { agent, .. } => {
+                if let Some(entity) = entities.agent(agent) {
+                    if let Some(carrier) = entities.carriers.get_mut(entity) {
+                        carrier.carrying = None;
+                    }
+                    if let Some(agent) = entities.agents.get_mut(entity) {
+                        agent.state = AgentState::MoveToTile;
+                    }
+                }
+            }
+            Event::TileStolen { agent, from, .. } => {
+                let (Some(thief), Some(victim)) = (entities.agent(agent), entities.agent(from))
+                else {
+                    return;
+                };
+                let value = entities
+                    .carriers
+                    .get_mut(victim)
+                    .and_then(|c| c.carrying.take());
+                if let Some(carrier) = entities.carriers.get_mut(thief) {
+                    carrier.carrying = value;
+                }
+                for (entity, state) in [
+                    (thief, AgentState::MoveToHole),
+                    (victim, AgentState::MoveToTile),
+                ] {
+                    if let Some(agent) = entities.agents.get_mut(entity) {
+                        agent.state = state;
+                    }
+                }
+            }
+            Event::HoleFilled { agent, points, .. } => {
+                if let Some(entity) = entities.agent(agent) {
+                    if let Some(agent) = entities.agents.get_mut(entity) {
+                        agent.score += points;
+                    }
+                }
+            }
+            Event::EntityRemoved { id, .. } => entities.despawn(id),
+            Event::EntityExpired { .. } | Event::PathNotFound { .. } => {}
+        }
+    }
+}
+
+/// Plays back a run recorded with `--events`, tick 0 is the initial world and the worlds
+/// started by a reset follow one after another.
+pub(crate) struct Replay {
+    ticks: Vec<Vec<Event>>,
+    world: World,
+    /// the last tick applied to `world`
+    position: usize,
+    paused: bool,
+    speed: usize,
+    frame_time: f32,
+}
+
+impl Replay {
+    pub(crate) fn load(path: &str) -> io::Result<Replay> {
+        let mut records = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            records.push(serde_json::from_str(&line)?);
+        }
+        Ok(Replay::from_records(records))
+    }
+
+    fn from_records(records: Vec<Record>) -> Replay {
+        let mut ticks: Vec<Vec<Event>> = vec![Vec::new()];
+        // where the current world's ticks start, a world after a reset counts from 0 again
+        let mut start = 0;
+        let mut created = false;
+        for record in records {
+            if let Event::WorldCreated { .. } = record.event {
+                if created {
+                    start = ticks.len().saturating_sub(record.tick as usize);
+                }
+                created = true;
+            }
+            let tick = start + record.tick as usize;
+            if tick >= ticks.len() {
+                ticks.resize_with(tick + 1, Vec::new);
+            }
+            ticks[tick].push(record.event);
+        }
+        Replay::new(ticks)
+    }
+
+    fn new(ticks: Vec<Vec<Event>>) -> Replay {
+        let mut replay = Replay {
+            ticks,
+            world: World::default(),
+            position: 0,
+            paused: true,
+            speed: 1,
+            frame_time: 0.0,
+        };
+        replay.seek(0);
+        replay
+    }
+
+    fn last(&self) -> usize {
+        self.ticks.len() - 1
+    }
+
+    /// Moves to `tick`, going back means rebuilding the world from the start.
+    fn seek(&mut self, tick: usize) {
+        let tick = tick.min(self.last());
+        let from = if tick < self.position || tick == 0 {
+            self.world = World::default();
+            0
+        } else {
+            self.position + 1
+        };
+        for t in from..=tick {
+            for event in self.ticks[t].iter() {
+                self.world.apply(t as u64, event);
+            }
+        }
+        self.position = tick;
+    }
+
+    fn handle_key(&mut self, key: VirtualKeyCode) {
+        match key {
+            VirtualKeyCode::Space => self.paused = !self.paused,
+            VirtualKeyCode::Right | VirtualKeyCode::N => {
+                self.paused = true;
+                self.seek(self.position + 1);
+            }
+            VirtualKeyCode::Left | VirtualKeyCode::B => {
+                self.paused = true;
+                self.seek(self.position.saturating_sub(1));
+            }
+            VirtualKeyCode::Home => self.seek(0),
+            VirtualKeyCode::Equals | VirtualKeyCode::Plus | VirtualKeyCode::NumpadAdd => {
+                self.speed = (self.speed + 1).min(SPEEDS.len() - 1)
+            }
+            VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract => {
+                self.speed = self.speed.saturating_sub(1)
+            }
+            _ => {}
+        }
+    }
+
+    /// Size of the recorded grid, for sizing the window.
+    pub(crate) fn dimensions(&self) -> (u16, u16) {
+        (self.world.cols, self.world.rows)
+    }
+
+    fn render(&self, ctx: &mut BTerm) {
+        let (cols, rows) = self.dimensions();
+        ctx.cls_bg(WHITE);
+        systems::render(&self.world.entities, self.position as u64, ctx);
+        systems::render_legend(ctx, cols + 3, rows.saturating_sub(8));
+        let entities = &self.world.entities;
+        let height = rows.saturating_sub(11);
+        dashboard::agents(
+            entities,
+            self.position as u64,
+            None,
+            None,
+            (cols + 3, 3),
+            height,
+            ctx,
+        );
+        ctx.print_color(
+            cols + 3,
+            1,
+            BLACK,
+            WHITE,
+            format!("Tick {}/{}", self.position, self.last()),
+        );
+        let status = if self.paused { "paused" } else { "playing" };
+        ctx.print_color(
+            cols + 3,
+            rows.saturating_sub(5),
+            BLACK,
+            WHITE,
+            format!("{} x{}", status, SPEEDS[self.speed]),
+        );
+        ctx.print_color(
+            cols + 3,
+            rows.saturating_sub(3),
+            BLACK,
+            WHITE,
+            "space: pause",
+        );
+        ctx.print_color(
+            cols + 3,
+            rows.saturating_sub(2),
+            BLACK,
+            WHITE,
+            "<- ->: step",
+        );
+        ctx.print_color(cols + 3, rows.saturating_sub(1), BLACK, WHITE, "+ -: speed");
+    }
+}
+
+impl GameState for Replay {
+    fn tick(&mut self, ctx: &mut BTerm) {
+        if let Some(key) = ctx.key {
+            self.handle_key(key);
+        }
+        self.frame_time += ctx.frame_time_ms;
+        if self.frame_time > 1000.0 / 5.0 / SPEEDS[self.speed] {
+            self.frame_time = 0.0;
+            if !self.paused {
+                if self.position < self.last() {
+                    self.seek(self.position + 1);
+                } else {
+                    self.paused = true;
+                }
+            }
+        }
+        self.render(ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::Location;
+
+    fn spawn(kind: EntityKind, id: usize, col: u16, score: Option<u8>) -> Event {
+        Event::EntitySpawned {
+            kind,
+            id,
+            location: Location::new(col, 0),
+            score,
+        }
+    }
+
+    fn replay() -> Replay {
+        Replay::new(vec![
+            vec![
+                spawn(EntityKind::Agent, 0, 0, None),
+                spawn(EntityKind::Tile, 1, 1, Some(3)),
+                spawn(EntityKind::Hole, 2, 5, Some(2)),
+            ],
+            vec![
+                Event::AgentMoved {
+                    agent: 0,
+                    from: Location::new(0, 0),
+                    to: Location::new(1, 0),
+                },
+                Event::TilePickedUp {
+                    agent: 0,
+                    tile: 1,
+                    location: Location::new(1, 0),
+                    value: 3,
+                },
+                spawn(EntityKind::Tile, 1, 9, Some(1)),
+            ],
+            vec![Event::HoleFilled {
+                agent: 0,
+                hole: 2,
+                location: Location::new(5, 0),
+                points: 5,
+            }],
+        ])
+    }
+
+    fn location(replay: &Replay, entity: usize) -> Location {
+        *replay.world.entities.positions.get(entity).unwrap()
+    }
+
+    fn score(replay: &Replay) -> u32 {
+        replay.world.entities.agents.get(0).unwrap().score
+    }
+
+    #[test]
+    fn test_step_forward() {
+        let mut replay = replay();
+        assert_eq!(Location::new(0, 0), location(&replay, 0));
+        replay.seek(1);
+        assert_eq!(Location::new(1, 0), location(&replay, 0));
+        let carrying = replay.world.entities.carriers.get(0).unwrap().carrying;
+        assert_eq!(3, carrying.unwrap().score);
+        assert_eq!(Location::new(9, 0), location(&replay, 1));
+        replay.seek(2);
+        assert_eq!(5, score(&replay));
+        // can't go past the end
+        replay.seek(10);
+        assert_eq!(2, replay.position);
+        assert_eq!(5, score(&replay));
+    }
+
+    #[test]
+    fn test_decay() {
+        let decay = Decay {
+            tile_interval: 2,
+            ..Default::default()
+        };
+        let mut replay = Replay::new(vec![
+            vec![
+                Event::WorldCreated {
+                    cols: 10,
+                    rows: 1,
+                    decay,
+                },
+                spawn(EntityKind::Tile, 0, 1, Some(3)),
+            ],
+            vec![],
+            vec![],
+        ]);
+        replay.seek(2);
+        let value = replay.world.entities.values.get(0).unwrap();
+        assert_eq!(2, value.at(replay.position as u64));
+    }
+
+    #[test]
+    fn test_reset() {
+        let record = |tick, event| Record { tick, event };
+        let created = Event::WorldCreated {
+            cols: 10,
+            rows: 1,
+            decay: Decay::default(),
+        };
+        let mut replay = Replay::from_records(vec![
+            record(0, created.clone()),
+            record(0, spawn(EntityKind::Agent, 0, 0, None)),
+            record(0, spawn(EntityKind::Tile, 1, 1, Some(3))),
+            record(
+                1,
+                Event::AgentMoved {
+                    agent: 0,
+                    from: Location::new(0, 0),
+                    to: Location::new(1, 0),
+                },
+            ),
+            record(0, created),
+            record(0, spawn(EntityKind::Agent, 0, 4, None)),
+        ]);
+        assert_eq!(2, replay.last());
+        replay.seek(1);
+        assert_eq!(Location::new(1, 0), location(&replay, 0));
+        replay.seek(2);
+        assert_eq!(Location::new(4, 0), location(&replay, 0));
+        assert!(!replay.world.entities.kinds.contains(1));
+    }
+
+    #[test]
+    fn test_step_back() {
+        let mut replay = replay();
+        replay.seek(2);
+        replay.seek(1);
+        assert_eq!(0, score(&replay));
+        assert_eq!(1, replay.world.entities.pickups.len());
+        replay.seek(0);
+        assert_eq!(Location::new(0, 0), location(&replay, 0));
+        assert!(replay
+            .world
+            .entities
+            .carriers
+            .get(0)
+            .unwrap()
+            .carrying
+            .is_none());
+        assert_eq!(Location::new(1, 0), location(&replay, 1));
+    }
+}
@@ -0,0 +1,140 @@
+use std::str::FromStr;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    agent::{Action, AgentId, Plan},
+    ecs::Entity,
+    location::{Direction, Location},
+    world::World,
+};
+
+/// How the agents take their turns within a tick.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Schedule {
+    /// one after the other, each sees what the ones before it did
+    #[default]
+    Sequential,
+    /// all decide on the same world, then the moves are carried out together
+    Simultaneous,
+}
+
+/// Who gets a cell when more than one agent moves into it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum TieBreak {
+    #[default]
+    Random,
+    /// the agent with priority moves on by one every tick
+    RoundRobin,
+    /// the lowest agent id wins
+    Priority,
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sequential" => Ok(Schedule::Sequential),
+            "simultaneous" => Ok(Schedule::Simultaneous),
+            _ => Err(format!("unknown schedule: {}", s)),
+        }
+    }
+}
+
+impl FromStr for TieBreak {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(TieBreak::Random),
+            "round-robin" => Ok(TieBreak::RoundRobin),
+            "priority" => Ok(TieBreak::Priority),
+            _ => Err(format!("unknown tie-break: {}", s)),
+        }
+    }
+}
+
+impl TieBreak {
+    /// Index of the winner among the agents with `ids`.
+    fn pick(&self, ids: &[AgentId], tick: u64, agents: u64, rng: &mut impl Rng) -> usize {
+        let rank = |id: AgentId| match self {
+            TieBreak::RoundRobin => (id as u64 + agents - tick % agents) % agents,
+            _ => id as u64,
+        };
+        match self {
+            // an uncontested cell leaves the random numbers for everything else
+            _ if ids.len() < 2 => 0,
+            TieBreak::Random => rng.gen_range(0..ids.len()),
+            _ => (0..ids.len()).min_by_key(|&i| rank(ids[i])).unwrap_or(0),
+        }
+    }
+}
+
+/// Settles who moves where when every agent decided on the same world.
+/// Returns the moves that go ahead and the agents that lost a cell to another one.
+pub(crate) fn resolve(
+    world: &mut World,
+    plans: &[(Entity, Plan)],
+) -> (Vec<(Entity, Direction)>, Vec<Entity>) {
+    let mut contested: Vec<(Location, Vec<(Entity, Direction)>)> = Vec::new();
+    for (entity, plan) in plans.iter() {
+        let Action::Move(direction) = plan.action else {
+            continue;
+        };
+        let Some(from) = world.entities.positions.get(*entity) else {
+            continue;
+        };
+        let to = from.next_location(direction);
+        match contested.iter_mut().find(|(l, _)| *l == to) {
+            Some((_, movers)) => movers.push((*entity, direction)),
+            None => contested.push((to, vec![(*entity, direction)])),
+        }
+    }
+    let agents = (world.config.agents as u64).max(1);
+    let mut moves = Vec::new();
+    let mut losers = Vec::new();
+    for (_, movers) in contested {
+        let ids: Vec<AgentId> = movers
+            .iter()
+            .map(|(e, _)| world.entities.agents.get(*e).map_or(0, |a| a.id))
+            .collect();
+        let winner = world
+            .config
+            .tie_break
+            .pick(&ids, world.tick, agents, &mut world.rng);
+        for (i, mover) in movers.into_iter().enumerate() {
+            if i == winner {
+                moves.push(mover);
+            } else {
+                losers.push(mover.0);
+            }
+        }
+    }
+    (moves, losers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_pick() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let ids = [3, 1, 2];
+        assert_eq!(1, TieBreak::Priority.pick(&ids, 0, 4, &mut rng));
+        assert_eq!(1, TieBreak::Priority.pick(&ids, 5, 4, &mut rng));
+        // agent 2 has priority on tick 2, then agent 3
+        assert_eq!(2, TieBreak::RoundRobin.pick(&ids, 2, 4, &mut rng));
+        assert_eq!(0, TieBreak::RoundRobin.pick(&ids, 3, 4, &mut rng));
+        // wraps around to agent 0, which isn't contending, so 1 is next
+        assert_eq!(1, TieBreak::RoundRobin.pick(&ids, 4, 4, &mut rng));
+        assert!(TieBreak::Random.pick(&ids, 0, 4, &mut rng) < 3);
+        let before = rng.clone();
+        assert_eq!(0, TieBreak::Random.pick(&[3], 0, 4, &mut rng));
+        assert_eq!(before, rng);
+    }
+}
@@ -0,0 +1,332 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    agent::{Action, AgentId, Plan},
+    ecs::Entity,
+    events::EntityKind,
+    location::Location,
+    message::{self, Outgoing},
+    world::World,
+};
+
+/// Sent to controllers as JSON Lines.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+enum Message {
+    /// the connection controls `agent` from now on
+    Welcome {
+        agent: AgentId,
+        cols: u16,
+        rows: u16,
+    },
+    /// every agent already has a controller, the connection gets closed
+    Full,
+    /// the world before `tick`, answer with a `Reply` for the same tick
+    Observation {
+        tick: u64,
+        agent: AgentId,
+        location: Location,
+        carrying: Option<u8>,
+        score: u32,
+        entities: Vec<Seen>,
+        /// what other agents told this one, sent last tick or earlier with `--message-delay`
+        messages: Vec<message::Message>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct Seen {
+    id: Entity,
+    kind: EntityKind,
+    location: Location,
+    value: Option<u8>,
+}
+
+/// What a controller sends back, e.g. `{"tick": 3, "action": {"Move": "Up"}}` or `{"tick": 3, "action": "Wait"}`,
+/// optionally with `"messages": [{"to": 2, "content": {"Claim": {"col": 4, "row": 1}}}]` for other agents.
+#[derive(Debug, Deserialize)]
+struct Reply {
+    tick: u64,
+    action: Action,
+    #[serde(default)]
+    messages: Vec<Outgoing>,
+}
+
+/// An agent driven from another process.
+struct Controller {
+    agent: AgentId,
+    stream: TcpStream,
+    replies: Receiver<Reply>,
+}
+
+/// Lets processes connect and control agents, each connection takes the next agent without a controller.
+/// Agents nobody controls decide for themselves.
+pub(crate) struct Server {
+    listener: TcpListener,
+    timeout: Duration,
+    controllers: Vec<Controller>,
+    reserved: Vec<AgentId>,
+}
+
+impl Server {
+    pub(crate) fn bind(addr: &str, timeout: Duration) -> io::Result<Server> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        info!("listening for controllers on {}", listener.local_addr()?);
+        Ok(Server {
+            listener,
+            timeout,
+            controllers: Vec::new(),
+            reserved: Vec::new(),
+        })
+    }
+
+    /// Blocks until `count` controllers are connected, or as many as there are agents to control.
+    pub(crate) fn wait_for(&mut self, world: &World, count: usize) {
+        let agents = world.entities.agents.iter();
+        let free = agents
+            .filter(|(_, a)| !self.reserved.contains(&a.id))
+            .count();
+        if count > free {
+            warn!(
+                "asked for {} controllers, there are only {} agents to control",
+                count, free
+            );
+        }
+        let count = count.min(free);
+        while self.controllers.len() < count {
+            self.accept(world);
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Keeps `agent` from being handed to a controller.
+    pub(crate) fn reserve(&mut self, agent: AgentId) {
+        self.reserved.push(agent);
+    }
+
+    /// Takes new controllers and gets the actions and messages of all of them for the next tick.
+    pub(crate) fn plans(&mut self, world: &World) -> HashMap<Entity, Plan> {
+        self.accept(world);
+        self.collect(world)
+    }
+
+    fn accept(&mut self, world: &World) {
+        while let Ok((stream, peer)) = self.listener.accept() {
+            let free = world
+                .entities
+                .agents
+                .iter()
+                .map(|(_, a)| a.id)
+                .filter(|id| !self.reserved.contains(id))
+                .find(|id| self.controllers.iter().all(|c| c.agent != *id));
+            let Some(agent) = free else {
+                warn!("{}: no agent left to control", peer);
+                let _ = send(&stream, &Message::Full);
+                continue;
+            };
+            match self.connect(stream, agent, world) {
+                Ok(controller) => {
+                    info!("{} controls agent {}", peer, agent);
+                    self.controllers.push(controller);
+                }
+                Err(e) => warn!("{}: {}", peer, e),
+            }
+        }
+    }
+
+    fn connect(&self, stream: TcpStream, agent: AgentId, world: &World) -> io::Result<Controller> {
+        stream.set_nonblocking(false)?;
+        stream.set_nodelay(true)?;
+        // a controller that stops reading would hold up every tick
+        stream.set_write_timeout(Some(self.timeout))?;
+        let (sender, replies) = mpsc::channel();
+        let reader = BufReader::new(stream.try_clone()?);
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                match serde_json::from_str(&line) {
+                    Ok(reply) => {
+                        if sender.send(reply).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => warn!("Agent {}: bad reply {:?}: {}", agent, line, e),
+                }
+            }
+        });
+        let welcome = Message::Welcome {
+            agent,
+            cols: world.config.cols,
+            rows: world.config.rows,
+        };
+        send(&stream, &welcome)?;
+        Ok(Controller {
+            agent,
+            stream,
+            replies,
+        })
+    }
+
+    /// Sends every controller its observation and waits up to the timeout for their actions.
+    fn collect(&mut self, world: &World) -> HashMap<Entity, Plan> {
+        let mut plans = HashMap::new();
+        let mut gone = Vec::new();
+        for (i, controller) in self.controllers.iter().enumerate() {
+            let Some(observation) = observe(world, controller.agent) else {
+                continue;
+            };
+            if let Err(e) = send(&controller.stream, &observation) {
+                warn!("Agent {}: controller went away: {}", controller.agent, e);
+                gone.push(i);
+            }
+        }
+        let deadline = Instant::now() + self.timeout;
+        for (i, controller) in self.controllers.iter().enumerate() {
+            if gone.contains(&i) {
+                continue;
+            }
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                match controller.replies.recv_timeout(remaining) {
+                    // a late answer to an earlier tick
+                    Ok(reply) if reply.tick != world.tick => continue,
+                    Ok(reply) => {
+                        if let Some(entity) = world.entities.agent(controller.agent) {
+                            let plan = Plan {
+                                action: reply.action,
+                                messages: reply.messages,
+                                ..Default::default()
+                            };
+                            plans.insert(entity, plan);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {
+                        warn!("Agent {}: no action in time", controller.agent)
+                    }
+                    Err(RecvTimeoutError::Disconnected) => {
+                        info!("Agent {}: controller disconnected", controller.agent);
+                        gone.push(i);
+                    }
+                }
+                break;
+            }
+        }
+        for i in gone.into_iter().rev() {
+            self.controllers.remove(i);
+        }
+        plans
+    }
+}
+
+fn observe(world: &World, agent: AgentId) -> Option<Message> {
+    let entities = &world.entities;
+    let entity = entities.agent(agent)?;
+    let seen = entities
+        .kinds
+        .iter()
+        .filter_map(|(id, &kind)| {
+            let location = *entities.positions.get(id)?;
+            // with `--sight` like for the agents built in, who always know where obstacles are
+            if kind != EntityKind::Obstacle && !world.sees(entity, location) {
+                return None;
+            }
+            Some(Seen {
+                id,
+                kind,
+                location,
+                value: entities.values.get(id).map(|v| v.at(world.tick)),
+            })
+        })
+        .collect();
+    Some(Message::Observation {
+        tick: world.tick,
+        agent,
+        location: *entities.positions.get(entity)?,
+        carrying: entities
+            .carriers
+            .get(entity)
+            .and_then(|c| c.carrying)
+            .map(|v| v.at(world.tick)),
+        score: entities.agents.get(entity)?.score,
+        entities: seen,
+        messages: world.channel.inbox(agent).to_vec(),
+    })
+}
+
+fn send(mut stream: &TcpStream, message: &Message) -> io::Result<()> {
+    serde_json::to_writer(stream, message)?;
+    stream.write_all(b"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        events::EventLog,
+        world::tests::{empty_world, put},
+    };
+
+    #[test]
+    fn test_sight() {
+        let mut world = empty_world();
+        world.config.teams.sight = Some(3);
+        put(&mut world, EntityKind::Tile, Location::new(1, 2), 2);
+        put(&mut world, EntityKind::Tile, Location::new(9, 9), 2);
+        put(&mut world, EntityKind::Obstacle, Location::new(20, 20), 0);
+        let Some(Message::Observation { entities, .. }) = observe(&world, 0) else {
+            panic!("no observation");
+        };
+        let seen: Vec<Location> = entities.iter().map(|s| s.location).collect();
+        let expected = [(0, 0), (1, 2), (20, 20)].map(|(col, row)| Location::new(col, row));
+        assert_eq!(expected.to_vec(), seen);
+    }
+
+    #[test]
+    fn test_controller() {
+        let config = Config {
+            agents: 1,
+            seed: Some(1),
+            ..Default::default()
+        };
+        let mut world = World::new(config, EventLog::default());
+        let mut server = Server::bind("127.0.0.1:0", Duration::from_secs(5)).unwrap();
+        let addr = server.listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            let mut lines = BufReader::new(stream.try_clone().unwrap()).lines();
+            let welcome = lines.next().unwrap().unwrap();
+            assert!(welcome.starts_with("{\"type\":\"Welcome\",\"agent\":0"));
+            for _ in 0..3 {
+                let line = lines.next().unwrap().unwrap();
+                let observation: serde_json::Value = serde_json::from_str(&line).unwrap();
+                let tick = observation["tick"].as_u64().unwrap();
+                writeln!(stream, "{{\"tick\": {}, \"action\": \"Wait\"}}", tick).unwrap();
+            }
+        });
+        server.wait_for(&world, 1);
+        let start = world.entities.positions.get(0).copied();
+        for _ in 0..3 {
+            let plans = server.plans(&world);
+            world.step_with(&plans);
+        }
+        client.join().unwrap();
+        // left to itself the agent would have gone for a tile
+        assert_eq!(start, world.entities.positions.get(0).copied());
+        let agent = world.entities.agents.get(0).unwrap();
+        assert_eq!(3, agent.metrics.idle_ticks);
+    }
+}
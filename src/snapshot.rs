@@ -0,0 +1,110 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufReader, BufWriter},
+};
+
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    decay::Decay,
+    ecs::{Entities, Entity},
+    location::Location,
+    message::Channel,
+};
+
+/// Everything needed to continue a run exactly where it was saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    pub cols: u16,
+    pub rows: u16,
+    pub tick: u64,
+    pub decay: Decay,
+    pub rng: ChaCha8Rng,
+    /// occupied cells, kept as they are so the grid comes back exactly
+    pub occupied: Vec<Location>,
+    pub entities: Entities,
+    /// what every agent was going for, agents keep going for it and teammates leave it alone
+    #[serde(default)]
+    pub targets: BTreeMap<Entity, Location>,
+    /// messages on their way and the ones to read next tick
+    #[serde(default)]
+    pub channel: Channel,
+}
+
+impl Snapshot {
+    pub(crate) fn save(&self, path: &str) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    pub(crate) fn load(path: &str) -> io::Result<Snapshot> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::Config, events::EventLog, world::World};
+
+    use super::*;
+
+    /// planning time is wall clock time, so leave it out when comparing runs
+    fn to_json(world: &World) -> String {
+        let mut snapshot = world.snapshot();
+        let agents = &mut snapshot.entities.agents;
+        for entity in agents.entities().collect::<Vec<_>>() {
+            agents.get_mut(entity).unwrap().metrics.planning_nanos = 0;
+        }
+        serde_json::to_string(&snapshot).unwrap()
+    }
+
+    /// Runs 50 ticks, saves, and checks the next 50 ticks go the same with and without restoring.
+    fn resume(config: Config) -> World {
+        let mut world = World::new(config.clone(), EventLog::default());
+        for _ in 0..50 {
+            world.step();
+        }
+        let json = serde_json::to_string(&world.snapshot()).unwrap();
+        let snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+        let mut resumed = World::restore(snapshot, config, EventLog::default());
+        assert_eq!(json, serde_json::to_string(&resumed.snapshot()).unwrap());
+        for _ in 0..50 {
+            world.step();
+            resumed.step();
+        }
+        assert_eq!(to_json(&world), to_json(&resumed));
+        world
+    }
+
+    #[test]
+    fn test_resume() {
+        resume(Config {
+            seed: Some(7),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_resume_messages() {
+        let mut config = Config {
+            seed: Some(7),
+            ..Default::default()
+        };
+        config.teams.count = 2;
+        config.teams.sight = Some(4);
+        config.comms.enabled = true;
+        config.comms.delay = 3;
+        let world = resume(config);
+        let sent: u64 = world
+            .entities
+            .agents
+            .iter()
+            .map(|(_, a)| a.metrics.messages_sent)
+            .sum();
+        assert!(sent > 0);
+    }
+}
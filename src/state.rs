@@ -1,101 +1,187 @@
-use bracket_lib::terminal::*;
+use std::collections::HashMap;
 
-use crate::agent::Agent;
-use crate::grid::Grid;
-use crate::hole::Hole;
-use crate::obstacle::Obstacle;
-use crate::tile::Tile;
-use crate::{NUM_AGENTS, NUM_HOLES, NUM_OBSTACLES, NUM_TILES};
+use bracket_lib::terminal::*;
 use rand::Rng;
-use std::cell::RefCell;
-use std::rc::Rc;
+
+use crate::{
+    agent::Plan, dashboard::Dashboard, editor::Editor, overlay::Overlays, player::Player,
+    server::Server, systems, world::World,
+};
+
+/// Simulation speeds to pick from, in multiples of 5 ticks a second.
+pub(crate) const SPEEDS: [f32; 6] = [0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+/// Ticks run in one frame at most, so a slow tick doesn't snowball.
+const MAX_TICKS_PER_FRAME: usize = 8;
 
 pub(crate) struct State {
     frame_time: f32,
-    pub grid: Rc<RefCell<Grid>>,
-    pub agents: Vec<Rc<RefCell<Agent>>>,
-    pub tiles: Vec<Rc<RefCell<Tile>>>,
-    pub holes: Vec<Rc<RefCell<Hole>>>,
-    obstacles: Vec<Obstacle>,
+    pub world: World,
+    server: Option<Server>,
+    player: Option<Player>,
+    editor: Editor,
+    overlays: Overlays,
+    dashboard: Dashboard,
+    paused: bool,
+    speed: usize,
 }
 
 impl GameState for State {
     fn tick(&mut self, ctx: &mut BTerm) {
-        self.frame_time += ctx.frame_time_ms;
-        if self.frame_time > 1000.0 / 5.0 {
-            self.frame_time = 0.0;
-            self.update(ctx);
+        self.editor.update(ctx, &mut self.world);
+        if let Some(key) = ctx.key {
+            self.handle_key(key, ctx.control);
         }
+        self.advance(ctx.frame_time_ms);
+        self.render(ctx);
     }
 }
 
 impl State {
-    pub(crate) fn new() -> Self {
-        let mut rng = rand::thread_rng();
-        let mut grid = Grid::new();
-        let mut agents = Vec::new();
-        for i in 0..NUM_AGENTS as u8 {
-            let location = grid.random_location();
-            grid.set(location);
-            let a = Agent::new(i, location);
-            agents.push(Rc::new(RefCell::new(a)));
-        }
-        let mut tiles = Vec::new();
-        for _ in 0..NUM_TILES as u8 {
-            let location = grid.random_location();
-            grid.set(location);
-            let score = rng.gen_range(1..5);
-            let a = Tile::new(location, score);
-            tiles.push(Rc::new(RefCell::new(a)));
+    pub(crate) fn new(world: World, server: Option<Server>, player: Option<Player>) -> Self {
+        State {
+            frame_time: 0.0,
+            world,
+            server,
+            player,
+            editor: Editor::default(),
+            overlays: Overlays::default(),
+            dashboard: Dashboard::default(),
+            paused: false,
+            speed: 1,
         }
-        let mut holes = Vec::new();
-        for _ in 0..NUM_HOLES as u8 {
-            let location = grid.random_location();
-            grid.set(location);
-            let a = Hole::new(location);
-            holes.push(Rc::new(RefCell::new(a)));
+    }
+
+    /// Runs the ticks that are due `elapsed` milliseconds after the last frame, unless paused.
+    pub(crate) fn advance(&mut self, elapsed: f32) {
+        if self.paused {
+            return;
         }
-        let mut obstacles: Vec<Obstacle> = Vec::new();
-        for _ in 0..NUM_OBSTACLES as u8 {
-            let location = grid.random_location();
-            grid.set(location);
-            let a = Obstacle::new(location);
-            obstacles.push(a);
+        self.frame_time += elapsed;
+        let interval = 1000.0 / 5.0 / SPEEDS[self.speed];
+        let mut ticks = 0;
+        while self.frame_time > interval && ticks < MAX_TICKS_PER_FRAME {
+            self.frame_time -= interval;
+            self.update();
+            ticks += 1;
         }
-        State {
-            frame_time: 0.0,
-            grid: Rc::new(RefCell::new(grid)),
-            agents,
-            tiles,
-            holes,
-            obstacles,
+        if ticks == MAX_TICKS_PER_FRAME {
+            self.frame_time = 0.0;
         }
     }
 
-    fn render(&self, ctx: &mut BTerm) {
-        ctx.cls_bg(WHITE);
-        for agent in self.agents.iter() {
-            let agent = agent.borrow();
-            agent.render(ctx);
+    pub(crate) fn handle_key(&mut self, key: VirtualKeyCode, control: bool) {
+        if self.editor.handle_key(key) || self.overlays.handle_key(key) {
+            return;
         }
-        for tile in self.tiles.iter() {
-            let tile = tile.borrow();
-            tile.render(ctx);
+        // the player walks down with S, so saving is ctrl-S then
+        if !control && self.player.as_mut().is_some_and(|p| p.handle_key(key)) {
+            return;
         }
-        for hole in self.holes.iter() {
-            let hole = hole.borrow();
-            hole.render(ctx);
+        match key {
+            VirtualKeyCode::Space => self.paused = !self.paused,
+            VirtualKeyCode::N => {
+                self.paused = true;
+                self.update();
+            }
+            VirtualKeyCode::Equals | VirtualKeyCode::Plus | VirtualKeyCode::NumpadAdd => {
+                self.speed = (self.speed + 1).min(SPEEDS.len() - 1)
+            }
+            VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract => {
+                self.speed = self.speed.saturating_sub(1)
+            }
+            VirtualKeyCode::R => self.reset(),
+            VirtualKeyCode::S => self.world.save(),
+            _ => {}
         }
-        for obstacle in self.obstacles.iter() {
-            obstacle.render(ctx);
+    }
+
+    /// Starts over with a new world from a new seed, the event log carries on and
+    /// replays play the worlds one after another.
+    fn reset(&mut self) {
+        let mut config = self.world.config.clone();
+        config.seed = Some(self.world.rng.gen());
+        let events = std::mem::take(&mut self.world.events);
+        self.world = World::new(config, events);
+        self.dashboard = Dashboard::default();
+        self.frame_time = 0.0;
+    }
+
+    fn render(&self, ctx: &mut BTerm) {
+        ctx.cls_bg(WHITE);
+        let rows = self.world.config.rows;
+        self.overlays.render_under(&self.world, ctx);
+        systems::render(&self.world.entities, self.world.tick, ctx);
+        systems::render_legend(ctx, self.world.config.cols + 3, rows.saturating_sub(6));
+        self.overlays.render_over(&self.world, ctx);
+        self.overlays.render_legend(&self.world, ctx);
+        self.editor.render(&self.world, ctx);
+        let player = self.player.as_ref().map(|p| p.agent);
+        self.dashboard.render(&self.world, player, ctx);
+        ctx.print_color(
+            0,
+            rows,
+            BLACK,
+            LIGHT_GRAY,
+            format!("{}  space n +/- r", self.status()),
+        );
+    }
+
+    /// The tick, whether it's running, the speed and the seed.
+    pub(crate) fn status(&self) -> String {
+        let status = if self.paused { "paused" } else { "running" };
+        let seed = self
+            .world
+            .config
+            .seed
+            .map_or("-".to_string(), |s| s.to_string());
+        format!(
+            "tick {} {} x{} seed {}",
+            self.world.tick, status, SPEEDS[self.speed], seed
+        )
+    }
+
+    fn update(&mut self) {
+        let mut plans = match self.server.as_mut() {
+            Some(server) => server.plans(&self.world),
+            None => HashMap::new(),
+        };
+        if let Some((entity, action)) = self.player.as_mut().and_then(|p| p.action(&self.world)) {
+            let plan = Plan {
+                action,
+                ..Default::default()
+            };
+            plans.insert(entity, plan);
         }
+        self.world.step_with(&plans);
+        self.dashboard.record(&self.world);
+        self.world.autosave();
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, events::EventLog};
 
-    fn update(&mut self, ctx: &mut BTerm) {
-        for agent in self.agents.iter() {
-            let mut agent = agent.borrow_mut();
-            agent.update(self);
+    #[test]
+    fn test_controls() {
+        let config = Config {
+            seed: Some(1),
+            ..Default::default()
+        };
+        let mut state = State::new(World::new(config, EventLog::default()), None, None);
+        state.handle_key(VirtualKeyCode::Space, false);
+        assert!(state.paused);
+        state.handle_key(VirtualKeyCode::N, false);
+        state.handle_key(VirtualKeyCode::N, false);
+        assert_eq!(2, state.world.tick);
+        for _ in 0..10 {
+            state.handle_key(VirtualKeyCode::Plus, false);
         }
-        self.render(ctx);
+        assert_eq!(SPEEDS.len() - 1, state.speed);
+        state.handle_key(VirtualKeyCode::R, false);
+        assert_eq!(0, state.world.tick);
+        assert_ne!(Some(1), state.world.config.seed);
+        assert!(state.paused);
     }
 }
@@ -0,0 +1,318 @@
+use bracket_color::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use bracket_lib::prelude::{to_cp437, BTerm};
+use log::debug;
+use rand::Rng;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::ecs::Entities;
+use crate::{
+    agent::AgentState, components::Value, ecs::Entity, events::Event, location::Direction,
+    world::World,
+};
+
+/// Moves entities whose value ran out or whose time is up somewhere else.
+pub(crate) fn expire(world: &mut World) {
+    let expired: Vec<Entity> = world
+        .entities
+        .values
+        .iter()
+        .filter(|(_, v)| v.expired(world.tick))
+        .map(|(e, _)| e)
+        .collect();
+    for entity in expired {
+        if let (Some(&kind), Some(&location)) = (
+            world.entities.kinds.get(entity),
+            world.entities.positions.get(entity),
+        ) {
+            world.events.emit(Event::EntityExpired {
+                kind,
+                id: entity,
+                location,
+            });
+            world.grid.remove(location);
+            respawn(world, entity);
+        }
+    }
+}
+
+/// Moves `entity` one cell if nothing is in the way, returns whether it moved.
+/// Carriers can also step onto a pickup when empty handed and onto a receptacle when carrying.
+/// Moving into another agent steals from it or swaps places with it when the team rules allow.
+pub(crate) fn movement(world: &mut World, entity: Entity, direction: Direction) -> bool {
+    let Some(&from) = world.entities.positions.get(entity) else {
+        return false;
+    };
+    if !world.grid.is_valid_move(from, direction) {
+        return false;
+    }
+    let to = from.next_location(direction);
+    let carrier = world.entities.carriers.get(entity).copied();
+    let there = world.entities.at(to);
+    if let Some(other) = there.filter(|&e| world.entities.agents.contains(e)) {
+        let teams = world.config.teams;
+        return if world.teammates(entity, other) {
+            teams.make_way && swap(world, entity, other)
+        } else {
+            teams.steal && steal(world, entity, other)
+        };
+    }
+    let can_enter = match (carrier, there) {
+        (_, None) => world.grid.is_free(to),
+        (Some(c), Some(e)) if c.carrying.is_none() => world.entities.pickups.contains(e),
+        (Some(_), Some(e)) => world.entities.receptacles.contains(e),
+        (None, Some(_)) => false,
+    };
+    if !can_enter {
+        return false;
+    }
+    world.grid.remove(from);
+    world.grid.set(to);
+    world.entities.positions.insert(entity, to);
+    if let Some(agent) = world.entities.agents.get_mut(entity) {
+        agent.metrics.distance += 1;
+        world.heatmap.traversed(to);
+        world.events.emit(Event::AgentMoved {
+            agent: agent.id,
+            from,
+            to,
+        });
+    }
+    if let Some(there) = there {
+        if world.entities.pickups.contains(there) {
+            pickup(world, entity, there);
+        } else {
+            scoring(world, entity, there);
+        }
+    }
+    true
+}
+
+/// `carrier` takes the value of `item`, which then turns up somewhere else.
+fn pickup(world: &mut World, carrier: Entity, item: Entity) {
+    let (Some(&value), Some(&location)) = (
+        world.entities.values.get(item),
+        world.entities.positions.get(carrier),
+    ) else {
+        return;
+    };
+    if let Some(c) = world.entities.carriers.get_mut(carrier) {
+        c.carrying = Some(value);
+    }
+    if let Some(agent) = world.entities.agents.get_mut(carrier) {
+        agent.state = AgentState::MoveToHole;
+        agent.metrics.picked_up(world.tick);
+        world.events.emit(Event::TilePickedUp {
+            agent: agent.id,
+            tile: item,
+            location,
+            value: value.at(world.tick),
+        });
+    }
+    // the carrier stands where the item was, so the cell stays occupied
+    respawn(world, item);
+}
+
+/// `thief` takes the tile `victim` carries if its own hands are empty, it stays where it is.
+fn steal(world: &mut World, thief: Entity, victim: Entity) -> bool {
+    let entities = &mut world.entities;
+    let thief_id = entities.agents.get(thief).map(|a| a.id);
+    if entities
+        .carriers
+        .get(thief)
+        .is_none_or(|c| c.carrying.is_some())
+        || entities
+            .carriers
+            .get(victim)
+            .is_some_and(|c| c.stolen_from.is_some() && c.stolen_from == thief_id)
+    {
+        return false;
+    }
+    let (Some(&location), Some(from)) = (
+        entities.positions.get(victim),
+        entities.agents.get_mut(victim),
+    ) else {
+        return false;
+    };
+    let Some(value) = entities.carriers.get_mut(victim).and_then(|c| {
+        c.stolen_from = None;
+        c.carrying.take()
+    }) else {
+        return false;
+    };
+    from.state = AgentState::MoveToTile;
+    from.metrics.carrying_since = None;
+    let from = from.id;
+    if let Some(c) = entities.carriers.get_mut(thief) {
+        c.carrying = Some(value);
+        c.stolen_from = Some(from);
+    }
+    if let Some(agent) = entities.agents.get_mut(thief) {
+        agent.state = AgentState::MoveToHole;
+        agent.metrics.picked_up(world.tick);
+        agent.metrics.tiles_stolen += 1;
+        world.events.emit(Event::TileStolen {
+            agent: agent.id,
+            from,
+            location,
+            value: value.at(world.tick),
+        });
+    }
+    true
+}
+
+/// `entity` and its teammate `other` trade places, the cells stay occupied.
+fn swap(world: &mut World, entity: Entity, other: Entity) -> bool {
+    let (Some(&a), Some(&b)) = (
+        world.entities.positions.get(entity),
+        world.entities.positions.get(other),
+    ) else {
+        return false;
+    };
+    for (e, from, to) in [(entity, a, b), (other, b, a)] {
+        world.entities.positions.insert(e, to);
+        if let Some(agent) = world.entities.agents.get_mut(e) {
+            agent.metrics.distance += 1;
+            world.heatmap.traversed(to);
+            world.events.emit(Event::AgentMoved {
+                agent: agent.id,
+                from,
+                to,
+            });
+        }
+    }
+    true
+}
+
+/// `carrier` drops what it carries into `receptacle` and scores the tile, plus the hole when holes age.
+fn scoring(world: &mut World, carrier: Entity, receptacle: Entity) {
+    let Some(&location) = world.entities.positions.get(carrier) else {
+        return;
+    };
+    let Some(carried) = world.entities.carriers.get_mut(carrier).and_then(|c| {
+        c.stolen_from = None;
+        c.carrying.take()
+    }) else {
+        return;
+    };
+    let tick = world.tick;
+    let carried_value = carried.at(tick);
+    let mut points = carried_value as u32;
+    if world.config.decay.scores_holes() {
+        points += world
+            .entities
+            .values
+            .get(receptacle)
+            .map_or(0, |v| v.at(tick)) as u32;
+    }
+    if let Some(agent) = world.entities.agents.get_mut(carrier) {
+        agent.score += points;
+        agent.state = AgentState::MoveToTile;
+        agent.metrics.delivered(tick);
+        debug!("Agent {}: Score: {}", agent.id, agent.score);
+        world.events.emit(Event::TileDropped {
+            agent: agent.id,
+            location,
+            value: carried_value,
+        });
+        world.events.emit(Event::HoleFilled {
+            agent: agent.id,
+            hole: receptacle,
+            location,
+            points,
+        });
+    }
+    respawn(world, receptacle);
+}
+
+/// Puts `entity` on a random free cell with a fresh value.
+fn respawn(world: &mut World, entity: Entity) {
+    let location = world.grid.random_location(&mut world.rng);
+    world.grid.set(location);
+    world.entities.positions.insert(entity, location);
+    let score = world.rng.gen_range(1..5);
+    if let Some(value) = world.entities.values.get_mut(entity) {
+        *value = value.renewed(score, world.tick);
+    }
+    if let Some(event) = world.entities.spawned_event(entity) {
+        world.events.emit(event);
+    }
+}
+
+/// Draws every entity that has a glyph, tiles and holes show what they're worth at `tick`.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn render(entities: &Entities, tick: u64, ctx: &mut BTerm) {
+    for (entity, glyph) in entities.glyphs.iter() {
+        let Some(location) = entities.positions.get(entity) else {
+            continue;
+        };
+        let value = entities.values.get(entity);
+        let (fg, bg, c) = match value {
+            Some(value) if entities.pickups.contains(entity) => {
+                let points = value.at(tick);
+                (tile_color(points), RGB::named(WHITE), digit(points))
+            }
+            Some(value) if entities.receptacles.contains(entity) => (
+                RGB::named(WHITE),
+                hole_color(value, tick),
+                digit(value.at(tick)),
+            ),
+            _ => {
+                let carrying = entities
+                    .carriers
+                    .get(entity)
+                    .is_some_and(|c| c.carrying.is_some());
+                let c = if carrying { 219 } else { glyph.glyph };
+                (glyph.rgb(), RGB::named(WHITE), c)
+            }
+        };
+        ctx.set(location.col, location.row, fg, bg, c);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn digit(value: u8) -> u16 {
+    to_cp437(char::from(b'0' + value.min(9)))
+}
+
+/// Yellow for 1 point tiles up to dark red for 4 and more.
+pub(crate) fn tile_color(value: u8) -> RGB {
+    let ramp = [GOLD, DARK_ORANGE, RED, DARK_RED];
+    RGB::named(ramp[(value.max(1) as usize - 1).min(ramp.len() - 1)])
+}
+
+/// How deep the hole is at `tick`, holes that close after a while fade out as they get older.
+pub(crate) fn hole_color(value: &Value, tick: u64) -> RGB {
+    let color = depth_color(value.at(tick));
+    match value.lifetime.filter(|l| *l > 0) {
+        Some(lifetime) => {
+            let age = tick.saturating_sub(value.spawned) as f32 / lifetime as f32;
+            color.lerp(RGB::named(LIGHT_GRAY), age.min(1.0) * 0.8)
+        }
+        None => color,
+    }
+}
+
+/// Light blue for shallow holes up to navy for deep ones.
+fn depth_color(value: u8) -> RGB {
+    let ramp = [LIGHTSKYBLUE, DODGER_BLUE, BLUE, NAVY];
+    RGB::named(ramp[(value.max(1) as usize - 1).min(ramp.len() - 1)])
+}
+
+/// What the tile and hole colors mean, on two rows from `(x, row)`.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn render_legend(ctx: &mut BTerm, x: u16, row: u16) {
+    ctx.print_color(x, row, BLACK, WHITE, "tiles");
+    ctx.print_color(x, row + 1, BLACK, WHITE, "holes");
+    for value in 1..=4 {
+        let col = x + 5 + value as u16 * 2;
+        ctx.set(col, row, tile_color(value), RGB::named(WHITE), digit(value));
+        ctx.set(
+            col,
+            row + 1,
+            RGB::named(WHITE),
+            depth_color(value),
+            digit(value),
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentId;
+
+/// How agents are split into teams and what they may do to each other.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Teams {
+    /// agent `id` is on team `id % count`, 0 puts every agent on a team of its own
+    pub count: AgentId,
+    /// teammates don't go for a tile or hole another one is already going for
+    pub share_claims: bool,
+    /// how many steps away agents see tiles and holes, everything when unset
+    pub sight: Option<u16>,
+    /// teammates see what each other see
+    pub share_sight: bool,
+    /// empty handed agents take the tile an agent of another team carries by moving into it
+    pub steal: bool,
+    /// teammates swap places instead of blocking each other, only other teams are in the way
+    pub make_way: bool,
+}
+
+impl Teams {
+    pub(crate) fn team(&self, agent: AgentId) -> AgentId {
+        match self.count {
+            0 => agent,
+            count => agent % count,
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{
+    agent::AgentId,
+    raster::{bytes, Color},
+    systems::{hole_color, tile_color},
+    world::World,
+};
+
+const EMPTY: Color = (96, 96, 96);
+const OBSTACLE: Color = (160, 160, 160);
+const WHITE: Color = (255, 255, 255);
+
+/// What a cell shows, two characters wide so the grid comes out about square.
+pub(crate) struct Cell {
+    pub text: [char; 2],
+    // only the terminal frontend draws the colors
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub fg: Color,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub bg: Option<Color>,
+}
+
+/// Agents are named `A` to `Z`, or `AA` to `ZZ` when there are more than 26 of them.
+/// Past 676 agents names repeat.
+pub(crate) fn name(id: AgentId, agents: usize) -> [char; 2] {
+    let letter = |n: AgentId| char::from(b'A' + (n % 26) as u8);
+    if agents <= 26 {
+        [letter(id), ' ']
+    } else {
+        [letter(id / 26), letter(id)]
+    }
+}
+
+/// Every cell of the grid, row by row: agents as their name followed by `+` when carrying, or in lower case
+/// for two letter names,
+/// `t3` for a tile worth 3, `h2` for a hole 2 deep, `##` for obstacles and `. ` for nothing.
+pub(crate) fn cells(world: &World) -> Vec<Cell> {
+    let cols = world.config.cols as usize;
+    let mut cells: Vec<Cell> = (0..cols * world.config.rows as usize)
+        .map(|_| Cell {
+            text: ['.', ' '],
+            fg: EMPTY,
+            bg: None,
+        })
+        .collect();
+    let entities = &world.entities;
+    let agents = entities.agents.len();
+    for (entity, &location) in entities.positions.iter() {
+        let value = entities.values.get(entity);
+        let digit = |v: u8| char::from(b'0' + v.min(9));
+        let cell = if let Some(agent) = entities.agents.get(entity) {
+            let carrying = entities
+                .carriers
+                .get(entity)
+                .is_some_and(|c| c.carrying.is_some());
+            let text = name(agent.id, agents);
+            if carrying {
+                Cell {
+                    text: match text {
+                        [letter, ' '] => [letter, '+'],
+                        text => text.map(|c| c.to_ascii_lowercase()),
+                    },
+                    fg: WHITE,
+                    bg: Some(agent.color()),
+                }
+            } else {
+                Cell {
+                    text,
+                    fg: agent.color(),
+                    bg: None,
+                }
+            }
+        } else if let (Some(value), true) = (value, entities.pickups.contains(entity)) {
+            let points = value.at(world.tick);
+            Cell {
+                text: ['t', digit(points)],
+                fg: bytes(tile_color(points)),
+                bg: None,
+            }
+        } else if let (Some(value), true) = (value, entities.receptacles.contains(entity)) {
+            Cell {
+                text: ['h', digit(value.at(world.tick))],
+                fg: WHITE,
+                bg: Some(bytes(hole_color(value, world.tick))),
+            }
+        } else {
+            Cell {
+                text: ['#', '#'],
+                fg: OBSTACLE,
+                bg: None,
+            }
+        };
+        cells[location.row as usize * cols + location.col as usize] = cell;
+    }
+    cells
+}
+
+/// The world as plain text for logs: the tick and every agent's score, then the grid.
+pub(crate) fn ascii(world: &World) -> String {
+    let mut text = format!("tick {}", world.tick);
+    for (team, score) in world.team_scores() {
+        text += &format!("  team {} {}", team, score);
+    }
+    let agents = world.entities.agents.len();
+    for (_, agent) in world.entities.agents.iter() {
+        let name: String = name(agent.id, agents).iter().collect();
+        text += &format!("  {} {}", name.trim_end(), agent.score);
+    }
+    for (i, cell) in cells(world).iter().enumerate() {
+        if i % world.config.cols as usize == 0 {
+            text.push('\n');
+        }
+        text.extend(cell.text);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        events::EntityKind,
+        location::Location,
+        world::tests::{put, sized_world},
+    };
+
+    #[test]
+    fn test_ascii() {
+        let mut world = sized_world(4, 2);
+        let tile = put(&mut world, EntityKind::Tile, Location::new(1, 0), 3);
+        put(&mut world, EntityKind::Hole, Location::new(2, 1), 2);
+        put(&mut world, EntityKind::Obstacle, Location::new(3, 1), 0);
+        let tile = *world.entities.values.get(tile).unwrap();
+        assert_eq!("tick 0  A 0\nA t3. . \n. . h2##", ascii(&world));
+        world.entities.carriers.get_mut(0).unwrap().carrying = Some(tile);
+        assert!(ascii(&world).contains("\nA+t"));
+        assert_eq!(['A', ' '], name(0, 26));
+        assert_eq!(['B', 'A'], name(26, 27));
+        assert_ne!(name(0, 300), name(26, 300));
+    }
+}
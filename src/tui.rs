@@ -0,0 +1,138 @@
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use bracket_lib::terminal::VirtualKeyCode;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{self, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use crate::{
+    agent::AgentId,
+    raster::Color,
+    state::State,
+    text::{cells, name},
+};
+
+/// How long to wait for a key before drawing the next frame.
+const FRAME: Duration = Duration::from_millis(33);
+
+/// Runs `state` in the terminal until `Q` or escape is pressed.
+pub(crate) fn run(mut state: State) -> io::Result<()> {
+    let mut out = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(out, EnterAlternateScreen, cursor::Hide)?;
+    let result = main_loop(&mut state, &mut out);
+    execute!(out, ResetColor, cursor::Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn main_loop(state: &mut State, out: &mut impl Write) -> io::Result<()> {
+    let mut last = Instant::now();
+    loop {
+        draw(state, out)?;
+        if event::poll(FRAME)? {
+            if let Event::Key(key) = event::read()? {
+                let control = key.modifiers.contains(KeyModifiers::CONTROL);
+                match key.code {
+                    _ if key.kind == KeyEventKind::Release => {}
+                    KeyCode::Esc | KeyCode::Char('q') => return Ok(()),
+                    // raw mode swallows the interrupt
+                    KeyCode::Char('c') if control => return Ok(()),
+                    code => {
+                        if let Some(key) = virtual_key(code) {
+                            state.handle_key(key, control);
+                        }
+                    }
+                }
+            }
+        }
+        let now = Instant::now();
+        state.advance(now.duration_since(last).as_secs_f32() * 1000.0);
+        last = now;
+    }
+}
+
+/// The window's key for a terminal key, for the keys that do something without a mouse.
+fn virtual_key(code: KeyCode) -> Option<VirtualKeyCode> {
+    let key = match code {
+        KeyCode::Up => VirtualKeyCode::Up,
+        KeyCode::Down => VirtualKeyCode::Down,
+        KeyCode::Left => VirtualKeyCode::Left,
+        KeyCode::Right => VirtualKeyCode::Right,
+        KeyCode::Char(c) => match c.to_ascii_lowercase() {
+            ' ' => VirtualKeyCode::Space,
+            'n' => VirtualKeyCode::N,
+            '+' => VirtualKeyCode::Plus,
+            '=' => VirtualKeyCode::Equals,
+            '-' => VirtualKeyCode::Minus,
+            'r' => VirtualKeyCode::R,
+            's' => VirtualKeyCode::S,
+            'w' => VirtualKeyCode::W,
+            'a' => VirtualKeyCode::A,
+            'd' => VirtualKeyCode::D,
+            'e' => VirtualKeyCode::E,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(key)
+}
+
+fn rgb((r, g, b): Color) -> style::Color {
+    style::Color::Rgb { r, g, b }
+}
+
+/// Draws the grid with the agents' scores next to it and the status line under it.
+fn draw(state: &State, out: &mut impl Write) -> io::Result<()> {
+    let world = &state.world;
+    let (cols, rows) = (world.config.cols, world.config.rows);
+    let scores: Vec<(AgentId, Color, u32)> = world
+        .entities
+        .agents
+        .iter()
+        .map(|(_, a)| (a.id, a.color(), a.score))
+        .collect();
+    let total: u32 = scores.iter().map(|(_, _, s)| s).sum();
+    for (row, line) in cells(world).chunks(cols as usize).enumerate() {
+        queue!(out, cursor::MoveTo(0, row as u16))?;
+        for cell in line {
+            queue!(out, SetForegroundColor(rgb(cell.fg)))?;
+            match cell.bg {
+                Some(bg) => queue!(out, SetBackgroundColor(rgb(bg)))?,
+                None => queue!(out, SetBackgroundColor(style::Color::Reset))?,
+            }
+            queue!(out, Print(cell.text[0]), Print(cell.text[1]))?;
+        }
+        queue!(out, ResetColor, Print("  "))?;
+        // the sidebar: the total, then one agent a row for as many as fit
+        match row {
+            0 => queue!(out, Print(format!("score {}", total)))?,
+            row => {
+                if let Some(&(id, color, score)) = scores.get(row - 1) {
+                    let name = name(id, scores.len());
+                    queue!(
+                        out,
+                        SetForegroundColor(rgb(color)),
+                        Print(format!("{}{} {:>5}", name[0], name[1], score)),
+                        ResetColor
+                    )?;
+                }
+            }
+        }
+        queue!(out, terminal::Clear(ClearType::UntilNewLine))?;
+    }
+    queue!(
+        out,
+        cursor::MoveTo(0, rows),
+        Print(format!("{}  space n +/- r s q", state.status())),
+        terminal::Clear(ClearType::UntilNewLine)
+    )?;
+    out.flush()
+}
@@ -0,0 +1,83 @@
+use rand::Rng;
+use wasm_bindgen::prelude::*;
+
+use crate::{config::Config, events::EventLog, raster, world::World};
+
+/// A world running in the browser, `web/index.html` steps it and draws its frames on a canvas.
+#[wasm_bindgen]
+pub struct Demo {
+    world: World,
+    width: usize,
+    height: usize,
+}
+
+#[wasm_bindgen]
+impl Demo {
+    /// Takes the options from a URL query string, `?agents=10&seed=3` runs like
+    /// `--agents 10 --seed 3`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(query: &str) -> Result<Demo, JsError> {
+        let config = Config::from_args(args(query).into_iter()).map_err(|e| JsError::new(&e))?;
+        Ok(Demo {
+            world: World::new(config, EventLog::default()),
+            width: 0,
+            height: 0,
+        })
+    }
+
+    pub fn step(&mut self) {
+        self.world.step();
+    }
+
+    /// Starts over with a new world from a new seed.
+    pub fn reset(&mut self) {
+        let mut config = self.world.config.clone();
+        config.seed = Some(self.world.rng.gen());
+        self.world = World::new(config, EventLog::default());
+    }
+
+    pub fn tick(&self) -> u32 {
+        self.world.tick as u32
+    }
+
+    pub fn score(&self) -> u32 {
+        self.world
+            .entities
+            .agents
+            .iter()
+            .map(|(_, a)| a.score)
+            .sum()
+    }
+
+    /// The world as RGBA pixels for an `ImageData` of `width` by `height`.
+    pub fn frame(&mut self) -> Vec<u8> {
+        let image = raster::render(&self.world);
+        (self.width, self.height) = (image.width, image.height);
+        image
+            .pixels
+            .chunks(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect()
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
+/// Command line arguments for a query string, `?player` is a flag without a value.
+fn args(query: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    for pair in query.trim_start_matches('?').split('&') {
+        match pair.split_once('=') {
+            Some((name, value)) => args.extend([format!("--{}", name), value.to_string()]),
+            None if !pair.is_empty() => args.push(format!("--{}", pair)),
+            None => {}
+        }
+    }
+    args
+}
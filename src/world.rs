@@ -0,0 +1,765 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use log::debug;
+#[cfg(not(target_arch = "wasm32"))]
+use log::{error, info};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::agent::{Action, Agent, AgentId, Plan};
+use crate::config::Config;
+use crate::ecs::{Entities, Entity};
+use crate::events::{EntityKind, Event, EventLog};
+use crate::grid::Grid;
+use crate::heatmap::Heatmap;
+use crate::location::Location;
+use crate::message::{Channel, Message, Outgoing};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::metrics::{AgentReport, RunReport};
+use crate::schedule::{self, Schedule};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::snapshot::Snapshot;
+use crate::systems;
+use crate::text;
+
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_SNAPSHOT: &str = "snapshot.json";
+
+/// The simulation itself, owns everything in it so it can be sent to another thread.
+pub(crate) struct World {
+    pub config: Config,
+    pub tick: u64,
+    pub events: EventLog,
+    pub rng: ChaCha8Rng,
+    pub grid: Grid,
+    pub entities: Entities,
+    /// what each agent decided last, for drawing
+    pub plans: HashMap<Entity, Plan>,
+    pub heatmap: Heatmap,
+    /// messages between agents
+    pub channel: Channel,
+}
+
+impl World {
+    pub(crate) fn new(config: Config, events: EventLog) -> Self {
+        let rng = match config.seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_entropy(),
+        };
+        let grid = Grid::new(config.cols, config.rows);
+        let heatmap = Heatmap::new(config.cols, config.rows);
+        let mut world = World {
+            config,
+            tick: 0,
+            events,
+            rng,
+            grid,
+            entities: Entities::default(),
+            plans: HashMap::new(),
+            heatmap,
+            channel: Channel::default(),
+        };
+        for i in 0..world.config.agents {
+            let entity = world.spawn(EntityKind::Agent);
+            let agent = Agent::new(i, world.config.policy);
+            world.entities.add_agent(entity, agent);
+        }
+        for _ in 0..world.config.tiles {
+            world.spawn(EntityKind::Tile);
+        }
+        for _ in 0..world.config.holes {
+            world.spawn(EntityKind::Hole);
+        }
+        for _ in 0..world.config.obstacles {
+            world.spawn(EntityKind::Obstacle);
+        }
+        world.emit_spawned();
+        world
+    }
+
+    /// Creates an entity of `kind` on a random free cell.
+    fn spawn(&mut self, kind: EntityKind) -> Entity {
+        let location = self.grid.random_location(&mut self.rng);
+        self.spawn_at(kind, location)
+    }
+
+    fn spawn_at(&mut self, kind: EntityKind, location: Location) -> Entity {
+        self.grid.set(location);
+        let score = match kind {
+            EntityKind::Tile | EntityKind::Hole => self.rng.gen_range(1..5),
+            _ => 0,
+        };
+        let entity = self.entities.create();
+        self.entities
+            .spawn(entity, kind, location, score, self.tick, &self.config.decay);
+        entity
+    }
+
+    /// Continues a saved run, the grid size and decay settings come from the snapshot.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn restore(snapshot: Snapshot, mut config: Config, events: EventLog) -> Self {
+        config.decay = snapshot.decay;
+        config.cols = snapshot.cols;
+        config.rows = snapshot.rows;
+        let mut grid = Grid::new(snapshot.cols, snapshot.rows);
+        let heatmap = Heatmap::new(snapshot.cols, snapshot.rows);
+        for location in snapshot.occupied {
+            grid.set(location);
+        }
+        let mut world = World {
+            config,
+            tick: snapshot.tick,
+            events,
+            rng: snapshot.rng,
+            grid,
+            entities: snapshot.entities,
+            plans: HashMap::new(),
+            heatmap,
+            channel: snapshot.channel,
+        };
+        for (entity, target) in snapshot.targets {
+            let plan = Plan {
+                target: Some(target),
+                ..Default::default()
+            };
+            world.plans.insert(entity, plan);
+        }
+        world.emit_spawned();
+        world
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            cols: self.config.cols,
+            rows: self.config.rows,
+            tick: self.tick,
+            decay: self.config.decay,
+            rng: self.rng.clone(),
+            occupied: self.grid.occupied().collect(),
+            entities: self.entities.clone(),
+            targets: self
+                .plans
+                .iter()
+                .filter_map(|(&entity, plan)| Some((entity, plan.target?)))
+                .collect(),
+            channel: self.channel.clone(),
+        }
+    }
+
+    /// Puts a new tile, hole or obstacle on `location` if the cell is free.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn place(&mut self, kind: EntityKind, location: Location) -> Option<Entity> {
+        if kind == EntityKind::Agent || !self.is_free(location) {
+            return None;
+        }
+        let entity = self.spawn_at(kind, location);
+        if let Some(event) = self.entities.spawned_event(entity) {
+            self.events.emit(event);
+        }
+        Some(entity)
+    }
+
+    /// Takes a tile, hole or obstacle out of the world, agents stay.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn remove(&mut self, entity: Entity) -> bool {
+        let (Some(&kind), Some(&location)) = (
+            self.entities.kinds.get(entity),
+            self.entities.positions.get(entity),
+        ) else {
+            return false;
+        };
+        if kind == EntityKind::Agent {
+            return false;
+        }
+        self.entities.despawn(entity);
+        self.grid.remove(location);
+        self.events.emit(Event::EntityRemoved {
+            kind,
+            id: entity,
+            location,
+        });
+        true
+    }
+
+    /// Puts an agent on the free cell `to` without it walking there.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn relocate(&mut self, entity: Entity, to: Location) -> bool {
+        let (Some(agent), Some(&from)) = (
+            self.entities.agents.get(entity),
+            self.entities.positions.get(entity),
+        ) else {
+            return false;
+        };
+        if !self.is_free(to) {
+            return false;
+        }
+        self.events.emit(Event::AgentMoved {
+            agent: agent.id,
+            from,
+            to,
+        });
+        self.grid.remove(from);
+        self.grid.set(to);
+        self.entities.positions.insert(entity, to);
+        true
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn is_free(&self, location: Location) -> bool {
+        location.col < self.config.cols
+            && location.row < self.config.rows
+            && self.grid.is_free(location)
+    }
+
+    /// The team of agent `entity`.
+    pub(crate) fn team(&self, entity: Entity) -> Option<AgentId> {
+        let agent = self.entities.agents.get(entity)?;
+        Some(self.config.teams.team(agent.id))
+    }
+
+    /// Whether `a` and `b` are different agents on the same team.
+    pub(crate) fn teammates(&self, a: Entity, b: Entity) -> bool {
+        a != b && self.team(a).is_some() && self.team(a) == self.team(b)
+    }
+
+    /// What every team scored, when there are teams.
+    pub(crate) fn team_scores(&self) -> BTreeMap<AgentId, u32> {
+        let mut scores = BTreeMap::new();
+        if self.config.teams.count > 0 {
+            for (_, agent) in self.entities.agents.iter() {
+                *scores.entry(self.config.teams.team(agent.id)).or_default() += agent.score;
+            }
+        }
+        scores
+    }
+
+    /// Whether `entity` or, when they share what they see, one of its teammates sees `location`.
+    pub(crate) fn sees(&self, entity: Entity, location: Location) -> bool {
+        let Some(sight) = self.config.teams.sight else {
+            return true;
+        };
+        let share = self.config.teams.share_sight;
+        self.entities
+            .located(&self.entities.agents)
+            .filter(|&(e, _)| e == entity || (share && self.teammates(entity, e)))
+            .any(|(_, l)| l.distance(location) <= sight)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn report(&self) -> RunReport {
+        let teams = self.config.teams;
+        let agents = self
+            .entities
+            .agents
+            .iter()
+            .map(|(_, a)| {
+                let team = (teams.count > 0).then(|| teams.team(a.id));
+                AgentReport::new(a.id, team, a.score, &a.metrics)
+            })
+            .collect();
+        RunReport::new(self.tick, agents)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn save(&self) {
+        let path = self.config.save.as_deref().unwrap_or(DEFAULT_SNAPSHOT);
+        match self.snapshot().save(path) {
+            Ok(()) => info!("saved tick {} to {}", self.tick, path),
+            Err(e) => error!("failed to save snapshot to {}: {}", path, e),
+        }
+    }
+
+    /// Saves a snapshot when one is due with `--save-every`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn autosave(&self) {
+        if let Some(every) = self.config.save_every {
+            if self.tick.is_multiple_of(every) {
+                self.save();
+            }
+        }
+    }
+
+    fn emit_spawned(&mut self) {
+        let events = &mut self.events;
+        events.begin_tick(self.tick);
+        events.emit(Event::WorldCreated {
+            cols: self.config.cols,
+            rows: self.config.rows,
+            decay: self.config.decay,
+        });
+        for entity in self.entities.kinds.entities() {
+            if let Some(event) = self.entities.spawned_event(entity) {
+                events.emit(event);
+            }
+        }
+        events.flush();
+    }
+
+    /// Advances the simulation by one tick with the agents deciding for themselves.
+    pub(crate) fn step(&mut self) {
+        self.advance(|world, entity| world.decide(entity));
+    }
+
+    /// Advances the simulation by one tick, `plan` says what each agent does.
+    pub(crate) fn advance(&mut self, plan: impl Fn(&World, Entity) -> Option<Plan>) {
+        self.tick += 1;
+        self.events.begin_tick(self.tick);
+        systems::expire(self);
+        let agents: Vec<Entity> = self.entities.agents.entities().collect();
+        match self.config.schedule {
+            Schedule::Sequential => {
+                for entity in agents {
+                    if let Some(plan) = plan(self, entity) {
+                        self.apply(entity, plan);
+                    }
+                }
+            }
+            Schedule::Simultaneous => {
+                let plans: Vec<(Entity, Plan)> = agents
+                    .into_iter()
+                    .filter_map(|e| plan(self, e).map(|plan| (e, plan)))
+                    .collect();
+                self.apply_all(plans);
+            }
+        }
+        self.deliver();
+        for (_, location) in self.entities.located(&self.entities.agents) {
+            self.heatmap.occupied(location);
+        }
+        self.events.flush();
+        if log::log_enabled!(log::Level::Debug) {
+            debug!("\n{}", text::ascii(self));
+        }
+    }
+
+    /// Advances one tick, agents in `plans` follow them and the others decide for themselves.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn step_with(&mut self, plans: &HashMap<Entity, Plan>) {
+        self.advance(|world, entity| match plans.get(&entity) {
+            Some(plan) => Some(plan.clone()),
+            None => world.decide(entity),
+        });
+    }
+
+    pub(crate) fn decide(&self, entity: Entity) -> Option<Plan> {
+        let agent = self.entities.agents.get(entity)?;
+        Some(agent.decide(entity, self))
+    }
+
+    /// Carries out what the agent `entity` decided.
+    pub(crate) fn apply(&mut self, entity: Entity, plan: Plan) {
+        self.record(entity, &plan);
+        let moved = match plan.action {
+            Action::Move(direction) => systems::movement(self, entity, direction),
+            Action::Wait => false,
+        };
+        if !moved {
+            self.idle(entity);
+        }
+    }
+
+    /// Carries out what all agents decided at once, see `schedule::resolve` for who wins a cell.
+    fn apply_all(&mut self, plans: Vec<(Entity, Plan)>) {
+        for (entity, plan) in plans.iter() {
+            self.record(*entity, plan);
+        }
+        let (mut pending, losers) = schedule::resolve(self, &plans);
+        for entity in losers {
+            self.idle(entity);
+        }
+        for (entity, plan) in plans.iter() {
+            if plan.action == Action::Wait {
+                self.idle(*entity);
+            }
+        }
+        // a move into a cell another agent is leaving works once that one has gone
+        // a teammate that made way by swapping places has moved this tick
+        let mut swapped = HashSet::new();
+        loop {
+            let before = pending.len();
+            pending.retain(|&(entity, direction)| {
+                if swapped.contains(&entity) {
+                    return false;
+                }
+                let from = self.entities.positions.get(entity).copied();
+                if !systems::movement(self, entity, direction) {
+                    return true;
+                }
+                swapped.extend(from.and_then(|l| self.entities.at(l)));
+                false
+            });
+            if pending.is_empty() || pending.len() == before {
+                break;
+            }
+        }
+        for (entity, _) in pending {
+            self.idle(entity);
+        }
+    }
+
+    fn record(&mut self, entity: Entity, plan: &Plan) {
+        self.send(entity, &plan.messages);
+        self.plans.insert(entity, plan.clone());
+        let from = self.entities.positions.get(entity).copied();
+        let Some(agent) = self.entities.agents.get_mut(entity) else {
+            return;
+        };
+        agent
+            .metrics
+            .planned(plan.expanded.len(), plan.planning_time);
+        if plan.failed() {
+            agent.metrics.failed_plans += 1;
+            if let (Some(from), Some(to)) = (from, plan.target) {
+                self.events.emit(Event::PathNotFound {
+                    agent: agent.id,
+                    from,
+                    to,
+                });
+            }
+        }
+    }
+
+    /// Hands agents the messages they read next tick, a message sent with a delay of N is read N ticks later.
+    fn deliver(&mut self) {
+        for (agent, count) in self.channel.deliver(self.tick + 1) {
+            let Some(entity) = self.entities.agent(agent) else {
+                continue;
+            };
+            if let Some(agent) = self.entities.agents.get_mut(entity) {
+                agent.metrics.messages_received += count as u64;
+            }
+        }
+    }
+
+    /// Sends `messages` from `entity` to the agents in range, as many as the bandwidth allows.
+    fn send(&mut self, entity: Entity, messages: &[Outgoing]) {
+        let comms = self.config.comms;
+        let (Some(agent), Some(&from)) = (
+            self.entities.agents.get(entity),
+            self.entities.positions.get(entity),
+        ) else {
+            return;
+        };
+        let allowed = comms
+            .bandwidth
+            .map_or(messages.len(), |b| messages.len().min(b as usize));
+        let sender = agent.id;
+        let teams = self.config.teams.count > 0;
+        let arrives = self.tick + comms.delay;
+        for outgoing in &messages[..allowed] {
+            let message = Message {
+                from: sender,
+                to: outgoing.to,
+                sent: self.tick,
+                content: outgoing.content,
+            };
+            for (e, location) in self.entities.located(&self.entities.agents) {
+                let Some(id) = self.entities.agents.get(e).map(|a| a.id) else {
+                    continue;
+                };
+                let reaches = comms.range.is_none_or(|r| from.distance(location) <= r);
+                // with teams broadcasts only reach teammates
+                let addressed = match outgoing.to {
+                    Some(to) => to == id,
+                    None => !teams || self.teammates(entity, e),
+                };
+                if id != sender && addressed && reaches {
+                    self.channel.post(arrives, id, message);
+                }
+            }
+        }
+        if let Some(agent) = self.entities.agents.get_mut(entity) {
+            agent.metrics.messages_sent += allowed as u64;
+            agent.metrics.messages_dropped += (messages.len() - allowed) as u64;
+        }
+    }
+
+    fn idle(&mut self, entity: Entity) {
+        if let Some(agent) = self.entities.agents.get_mut(entity) {
+            agent.metrics.idle_ticks += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::agent::AgentState;
+    use crate::location::Direction;
+    use crate::message::{Comms, Content};
+    use crate::schedule::TieBreak;
+    use crate::{COLS, ROWS};
+
+    /// A world with a single agent in the top left corner, it is entity 0.
+    pub(crate) fn empty_world() -> World {
+        sized_world(COLS, ROWS)
+    }
+
+    /// `empty_world` on a `cols x rows` grid.
+    pub(crate) fn sized_world(cols: u16, rows: u16) -> World {
+        let config = Config {
+            cols,
+            rows,
+            agents: 1,
+            tiles: 0,
+            holes: 0,
+            obstacles: 0,
+            seed: Some(1),
+            ..Default::default()
+        };
+        let mut world = World::new(config, EventLog::default());
+        let location = world.entities.positions.get(0).copied().unwrap();
+        world.grid.remove(location);
+        world.entities.positions.insert(0, Location::new(0, 0));
+        world.grid.set(Location::new(0, 0));
+        world
+    }
+
+    /// Spawns `kind` on `location` worth `score`.
+    pub(crate) fn put(
+        world: &mut World,
+        kind: EntityKind,
+        location: Location,
+        score: u8,
+    ) -> Entity {
+        let entity = world.entities.create();
+        let decay = world.config.decay;
+        world
+            .entities
+            .spawn(entity, kind, location, score, world.tick, &decay);
+        world.grid.set(location);
+        entity
+    }
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn test_world_is_send() {
+        assert_send::<World>();
+    }
+
+    #[test]
+    fn test_pick_up_and_fill() {
+        let mut world = empty_world();
+        let tile = put(&mut world, EntityKind::Tile, Location::new(1, 0), 3);
+        let hole = put(&mut world, EntityKind::Hole, Location::new(1, 2), 2);
+
+        world.step();
+        assert_eq!(Some(&Location::new(1, 0)), world.entities.positions.get(0));
+        let carrying = world.entities.carriers.get(0).unwrap().carrying;
+        assert_eq!(3, carrying.unwrap().score);
+        assert_eq!(
+            AgentState::MoveToHole,
+            world.entities.agents.get(0).unwrap().state
+        );
+        assert!(!world.grid.is_free(Location::new(1, 0)));
+        assert_ne!(
+            Some(&Location::new(1, 0)),
+            world.entities.positions.get(tile)
+        );
+
+        world.step();
+        world.step();
+        assert_eq!(Some(&Location::new(1, 2)), world.entities.positions.get(0));
+        // holes don't age, so only the tile scores
+        assert_eq!(3, world.entities.agents.get(0).unwrap().score);
+        assert!(world.entities.carriers.get(0).unwrap().carrying.is_none());
+        assert_ne!(
+            Some(&Location::new(1, 2)),
+            world.entities.positions.get(hole)
+        );
+    }
+
+    #[test]
+    fn test_blocked_move() {
+        let mut world = empty_world();
+        put(&mut world, EntityKind::Obstacle, Location::new(1, 0), 0);
+        assert!(!systems::movement(&mut world, 0, Direction::Right));
+        assert!(!systems::movement(&mut world, 0, Direction::Up));
+        assert!(systems::movement(&mut world, 0, Direction::Down));
+        assert_eq!(Some(&Location::new(0, 1)), world.entities.positions.get(0));
+        assert!(world.grid.is_free(Location::new(0, 0)));
+    }
+
+    #[test]
+    fn test_simultaneous() {
+        let mut world = empty_world();
+        world.config.schedule = Schedule::Simultaneous;
+        world.config.tie_break = TieBreak::Priority;
+        let second = put(&mut world, EntityKind::Agent, Location::new(2, 0), 0);
+        world
+            .entities
+            .add_agent(second, Agent::new(1, world.config.policy));
+        let tile = put(&mut world, EntityKind::Tile, Location::new(1, 0), 3);
+
+        world.step();
+        // both went for the tile, agent 0 has priority
+        assert_eq!(Some(&Location::new(1, 0)), world.entities.positions.get(0));
+        assert_eq!(
+            Some(&Location::new(2, 0)),
+            world.entities.positions.get(second)
+        );
+        assert!(world.entities.carriers.get(0).unwrap().carrying.is_some());
+        assert!(world
+            .entities
+            .carriers
+            .get(second)
+            .unwrap()
+            .carrying
+            .is_none());
+        assert_eq!(
+            1,
+            world
+                .entities
+                .agents
+                .get(second)
+                .unwrap()
+                .metrics
+                .idle_ticks
+        );
+        assert_ne!(
+            Some(&Location::new(1, 0)),
+            world.entities.positions.get(tile)
+        );
+    }
+
+    #[test]
+    fn test_teams() {
+        let mut world = empty_world();
+        world.config.teams.count = 2;
+        let second = put(&mut world, EntityKind::Agent, Location::new(1, 1), 0);
+        world
+            .entities
+            .add_agent(second, Agent::new(1, world.config.policy));
+        put(&mut world, EntityKind::Tile, Location::new(1, 0), 3);
+        assert!(systems::movement(&mut world, second, Direction::Up));
+        let carrying = |world: &World, e| world.entities.carriers.get(e).unwrap().carrying;
+        assert!(carrying(&world, second).is_some());
+
+        // other teams are in the way, unless they can be robbed
+        assert!(!systems::movement(&mut world, 0, Direction::Right));
+        world.config.teams.steal = true;
+        assert!(systems::movement(&mut world, 0, Direction::Right));
+        assert!(carrying(&world, 0).is_some());
+        assert!(carrying(&world, second).is_none());
+        assert_eq!(Some(&Location::new(0, 0)), world.entities.positions.get(0));
+        assert_eq!(
+            1,
+            world.entities.agents.get(0).unwrap().metrics.tiles_stolen
+        );
+        // and can't be taken straight back
+        assert!(!systems::movement(&mut world, second, Direction::Left));
+
+        // teammates make way
+        world.config.teams.count = 1;
+        assert!(!systems::movement(&mut world, 0, Direction::Right));
+        world.config.teams.make_way = true;
+        assert!(systems::movement(&mut world, 0, Direction::Right));
+        assert_eq!(Some(&Location::new(1, 0)), world.entities.positions.get(0));
+        assert_eq!(
+            Some(&Location::new(0, 0)),
+            world.entities.positions.get(second)
+        );
+        assert_eq!(BTreeMap::from([(0, 0)]), world.team_scores());
+    }
+
+    #[test]
+    fn test_messages() {
+        let mut world = empty_world();
+        world.config.comms = Comms {
+            range: Some(3),
+            bandwidth: Some(2),
+            delay: 3,
+            ..Default::default()
+        };
+        let near = put(&mut world, EntityKind::Agent, Location::new(0, 3), 0);
+        let far = put(&mut world, EntityKind::Agent, Location::new(0, 9), 0);
+        for (entity, id) in [(near, 1), (far, 2)] {
+            world
+                .entities
+                .add_agent(entity, Agent::new(id, world.config.policy));
+        }
+        let claim = |to| Outgoing {
+            to,
+            content: Content::Claim(Location::new(5, 5)),
+        };
+        let plan = Plan {
+            messages: vec![claim(None), claim(Some(2)), claim(Some(1))],
+            ..Default::default()
+        };
+        world.step_with(&HashMap::from([(0, plan)]));
+        // the broadcast reaches the agent in range, the message to the far one doesn't arrive
+        // and the third is over the bandwidth
+        let metrics = &world.entities.agents.get(0).unwrap().metrics;
+        assert_eq!((2, 1), (metrics.messages_sent, metrics.messages_dropped));
+
+        // sent in tick 1, read in tick 4
+        let mut read = Vec::new();
+        for _ in 0..5 {
+            if let [message] = world.channel.inbox(1) {
+                assert_eq!((0, None, 1), (message.from, message.to, message.sent));
+                read.push(world.tick + 1);
+            }
+            assert!(world.channel.inbox(2).is_empty());
+            world.step();
+        }
+        assert_eq!(vec![4], read);
+        let metrics = &world.entities.agents.get(near).unwrap().metrics;
+        assert_eq!(1, metrics.messages_received);
+    }
+
+    #[test]
+    fn test_team_messages() {
+        let mut world = empty_world();
+        world.config.teams.count = 2;
+        world.config.comms.bandwidth = Some(2);
+        for (id, col) in [(1, 3), (2, 6)] {
+            let entity = put(&mut world, EntityKind::Agent, Location::new(col, 0), 0);
+            world
+                .entities
+                .add_agent(entity, Agent::new(id, world.config.policy));
+        }
+        let broadcast = |col| Outgoing {
+            to: None,
+            content: Content::Tile(Location::new(col, 5)),
+        };
+        let plan = Plan {
+            messages: vec![broadcast(1), broadcast(2)],
+            ..Default::default()
+        };
+        world.step_with(&HashMap::from([(0, plan)]));
+        // both broadcasts fit the bandwidth and only reach the teammate
+        let metrics = &world.entities.agents.get(0).unwrap().metrics;
+        assert_eq!((2, 0), (metrics.messages_sent, metrics.messages_dropped));
+        assert!(world.channel.inbox(1).is_empty());
+        assert_eq!(2, world.channel.inbox(2).len());
+    }
+
+    #[test]
+    fn test_simultaneous_swap() {
+        let mut world = empty_world();
+        world.config.schedule = Schedule::Simultaneous;
+        world.config.teams.count = 1;
+        world.config.teams.make_way = true;
+        let second = put(&mut world, EntityKind::Agent, Location::new(1, 0), 0);
+        world
+            .entities
+            .add_agent(second, Agent::new(1, world.config.policy));
+        let right = Plan {
+            action: Action::Move(Direction::Right),
+            ..Default::default()
+        };
+        world.step_with(&HashMap::from([(0, right.clone()), (second, right)]));
+        // the second agent made way and doesn't also take its own step
+        assert_eq!(Some(&Location::new(1, 0)), world.entities.positions.get(0));
+        assert_eq!(
+            Some(&Location::new(0, 0)),
+            world.entities.positions.get(second)
+        );
+        assert_eq!(
+            1,
+            world.entities.agents.get(second).unwrap().metrics.distance
+        );
+    }
+}
@@ -0,0 +1,9 @@
+#!/bin/sh
+# Builds the browser version into web/pkg, try it with `python3 -m http.server -d web`.
+# Needs `rustup target add wasm32-unknown-unknown` and the wasm-bindgen CLI of the same version
+# as the wasm-bindgen crate in Cargo.lock.
+set -e
+cd "$(dirname "$0")/.."
+cargo build --release --lib --target wasm32-unknown-unknown
+wasm-bindgen --target web --no-typescript --out-dir web/pkg \
+    target/wasm32-unknown-unknown/release/tileworld_engine.wasm